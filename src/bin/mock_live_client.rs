@@ -0,0 +1,141 @@
+/*
+Stand-in for the League Live Client API (https://127.0.0.1:2999/liveclientdata).
+Serves fixture json over https so LoLLiveClient and the overlay can be run end to end
+on machines that can't run League (Linux CI, etc).
+
+    mock_live_client [--port 2999] [--bind 127.0.0.1] [--fixtures fixtures] [--ca-out mock-root.pem]
+    mock_live_client --script fixtures/scripts/demo.toml [--speed 4]
+
+--script plays a scripted game (see mock/synthetic.rs) instead of serving the fixture files as they are.
+The cert is made up fresh each run the same way Riot does theirs: a root, and a 127.0.0.1 cert
+with no subject alt names signed by it. --ca-out writes the root so the overlay can pin it with --ca-cert.
+*/
+use anyhow::{Context, Result, bail};
+use axum_server::tls_rustls::RustlsConfig;
+use league_overlay::mock::fixtures::Fixtures;
+use league_overlay::mock::server::{self, GameSource};
+use league_overlay::mock::synthetic::ScriptedGame;
+use std::net::{IpAddr, Ipv4Addr, SocketAddr};
+use std::path::PathBuf;
+use std::sync::Arc;
+
+struct Args {
+    bind: IpAddr,
+    port: u16,
+    fixtures: PathBuf,
+    script: Option<PathBuf>,
+    speed: Option<f64>,
+    ca_out: Option<PathBuf>,
+}
+
+const USAGE: &str = "mock_live_client [--port 2999] [--bind 127.0.0.1] [--fixtures fixtures | --script game.toml [--speed 1.0]] [--ca-out root.pem]";
+
+fn parse_args() -> Result<Args> {
+    let mut args = Args {
+        bind: IpAddr::V4(Ipv4Addr::LOCALHOST),
+        port: 2999,
+        fixtures: PathBuf::from("fixtures"),
+        script: None,
+        speed: None,
+        ca_out: None,
+    };
+
+    let mut iter = std::env::args().skip(1);
+    while let Some(flag) = iter.next() {
+        let mut value = || {
+            iter.next()
+                .with_context(|| format!("{} needs a value", flag))
+        };
+        match flag.as_str() {
+            "--bind" => args.bind = value()?.parse().context("--bind must be an ip address")?,
+            "--port" => args.port = value()?.parse().context("--port must be a number")?,
+            "--fixtures" => args.fixtures = PathBuf::from(value()?),
+            "--script" => args.script = Some(PathBuf::from(value()?)),
+            "--ca-out" => args.ca_out = Some(PathBuf::from(value()?)),
+            "--speed" => args.speed = Some(value()?.parse().context("--speed must be a number")?),
+            "-h" | "--help" => {
+                println!("{}", USAGE);
+                std::process::exit(0);
+            }
+            other => bail!("Unknown argument: {}", other),
+        }
+    }
+
+    Ok(args)
+}
+
+// Source errors are logged by the library, they belong on stdout with everything else
+struct StdoutLogger;
+
+impl log::Log for StdoutLogger {
+    fn enabled(&self, metadata: &log::Metadata) -> bool {
+        metadata.level() <= log::Level::Info && metadata.target().starts_with("league_overlay")
+    }
+
+    fn log(&self, record: &log::Record) {
+        if self.enabled(record.metadata()) {
+            println!("{}", record.args());
+        }
+    }
+
+    fn flush(&self) {}
+}
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    log::set_logger(&StdoutLogger)?;
+    log::set_max_level(log::LevelFilter::Info);
+    let args = parse_args()?;
+    let (source, description): (Arc<dyn GameSource>, String) = match &args.script {
+        Some(path) => {
+            let game = ScriptedGame::load(path, args.speed)?;
+            let description = format!("script {} at {}x", path.display(), game.speed());
+            (Arc::new(game), description)
+        }
+        None => (
+            Arc::new(Fixtures::open(&args.fixtures)?),
+            format!("fixtures from {}", args.fixtures.display()),
+        ),
+    };
+
+    let (root, cert) = generate_certs()?;
+    if let Some(path) = &args.ca_out {
+        std::fs::write(path, root.serialize_pem()?)
+            .with_context(|| format!("Failed to write {}", path.display()))?;
+        println!("Wrote the root certificate to {}", path.display());
+    }
+    let _ = rustls::crypto::ring::default_provider().install_default();
+    let tls = RustlsConfig::from_pem(
+        cert.serialize_pem_with_signer(&root)?.into_bytes(),
+        cert.serialize_private_key_pem().into_bytes(),
+    )
+    .await?;
+
+    let addr = SocketAddr::new(args.bind, args.port);
+    println!("Serving {} on https://{}/liveclientdata", description, addr);
+
+    axum_server::bind_rustls(addr, tls)
+        .serve(server::router(source).into_make_service())
+        .await?;
+
+    Ok(())
+}
+
+// A root and a leaf shaped like the game's: 127.0.0.1 in the common name only
+fn generate_certs() -> Result<(rcgen::Certificate, rcgen::Certificate)> {
+    let mut root = rcgen::CertificateParams::default();
+    root.is_ca = rcgen::IsCa::Ca(rcgen::BasicConstraints::Unconstrained);
+    root.distinguished_name = rcgen::DistinguishedName::new();
+    root.distinguished_name
+        .push(rcgen::DnType::CommonName, "Mock LoL Game Root CA");
+
+    let mut leaf = rcgen::CertificateParams::default();
+    leaf.distinguished_name = rcgen::DistinguishedName::new();
+    leaf.distinguished_name
+        .push(rcgen::DnType::CommonName, "127.0.0.1");
+
+    Ok((
+        rcgen::Certificate::from_params(root)?,
+        rcgen::Certificate::from_params(leaf)?,
+    ))
+}
@@ -0,0 +1,123 @@
+use crate::config::Config;
+use crate::placement::{Anchor, parse_offset};
+use anyhow::{Context, Result, bail};
+use eframe::egui::Vec2;
+use league_overlay::polling::PollConfig;
+use league_overlay::tls::CertTrust;
+use std::path::PathBuf;
+
+/*
+Command line options for the overlay
+    league_overlay [--record <path>] [--poll <key=ms,...>] [--ca-cert <path> | --insecure]
+                   [--anchor <top-right|...>] [--offset <x,y>] [--dragontail <path>] [--icons <path>]
+                   [--config <path>]
+    league_overlay --replay <path> [--speed <x>]
+    league_overlay --script <path> [--speed <x>]
+    league_overlay --stdin
+The last three pick a different data source, see source.rs
+--poll, --ca-cert, --insecure, --anchor, --offset, --dragontail and --icons override the config file (see config.rs),
+--anchor and --offset place the stats panel
+*/
+
+const USAGE: &str = "league_overlay [--record <path> | --replay <path> | --script <path> | --stdin] [--speed <x>] [--poll <key=ms,...>] [--ca-cert <path> | --insecure] [--anchor <corner>] [--offset <x,y>] [--dragontail <path>] [--icons <path>] [--config <path>]";
+
+#[derive(Default, Clone)]
+pub struct Args {
+    //Write every /allgamedata response here (see recorder.rs)
+    pub record: Option<PathBuf>,
+    //Play a recording instead of talking to the game (see replay.rs)
+    pub replay: Option<PathBuf>,
+    //Play a scripted game (see the library's mock/synthetic.rs)
+    pub script: Option<PathBuf>,
+    //Read allgamedata json lines from stdin
+    pub stdin: bool,
+    //Replay/script speed multiplier
+    pub speed: Option<f64>,
+    //Config file instead of the one in the user config directory
+    pub config: Option<PathBuf>,
+    //Live Client poll intervals, "key=ms,..." (see polling.rs)
+    pub poll: Option<String>,
+    //Which root the Live Client's cert has to chain to (see tls.rs)
+    pub cert_trust: Option<CertTrust>,
+    //Where on the monitor the overlay goes (see placement.rs)
+    pub anchor: Option<Anchor>,
+    pub offset: Option<Vec2>,
+    //Extracted Data Dragon bundle (see dragontail.rs)
+    pub dragontail: Option<PathBuf>,
+    //Folder of icon PNGs (see icons.rs)
+    pub icons: Option<PathBuf>,
+}
+
+impl Args {
+    pub fn parse() -> Result<Self> {
+        let mut args = Args::default();
+
+        let mut iter = std::env::args().skip(1);
+        while let Some(flag) = iter.next() {
+            let mut value = || {
+                iter.next()
+                    .with_context(|| format!("{} needs a value", flag))
+            };
+            match flag.as_str() {
+                "--record" => args.record = Some(PathBuf::from(value()?)),
+                "--replay" => args.replay = Some(PathBuf::from(value()?)),
+                "--script" => args.script = Some(PathBuf::from(value()?)),
+                "--stdin" => args.stdin = true,
+                "--speed" => {
+                    args.speed = Some(value()?.parse().context("--speed must be a number")?)
+                }
+                "--ca-cert" => args.set_cert_trust(CertTrust::CaFile(PathBuf::from(value()?)))?,
+                "--insecure" => args.set_cert_trust(CertTrust::Insecure)?,
+                "--anchor" => args.anchor = Some(value()?.parse()?),
+                "--offset" => args.offset = Some(parse_offset(&value()?)?),
+                "--poll" => args.poll = Some(value()?),
+                "--dragontail" => args.dragontail = Some(PathBuf::from(value()?)),
+                "--icons" => args.icons = Some(PathBuf::from(value()?)),
+                "--config" => args.config = Some(PathBuf::from(value()?)),
+                "-h" | "--help" => {
+                    println!("{}", USAGE);
+                    std::process::exit(0);
+                }
+                other => bail!("Unknown argument: {}\n{}", other, USAGE),
+            }
+        }
+
+        // Catch a bad --poll now rather than when the config is loaded
+        if let Some(poll) = &args.poll {
+            PollConfig::default().apply_overrides(poll)?;
+        }
+
+        Ok(args)
+    }
+
+    fn set_cert_trust(&mut self, trust: CertTrust) -> Result<()> {
+        if self.cert_trust.is_some() {
+            bail!("--ca-cert and --insecure can't be used together");
+        }
+        self.cert_trust = Some(trust);
+        Ok(())
+    }
+
+    /// Puts whatever was given on the command line over the config file's settings
+    pub fn apply_overrides(&self, config: &mut Config) -> Result<()> {
+        if let Some(poll) = &self.poll {
+            config.poll.apply_overrides(poll)?;
+        }
+        if let Some(trust) = &self.cert_trust {
+            config.live_client.set_cert_trust(trust);
+        }
+        if let Some(anchor) = self.anchor {
+            config.windows.stats.anchor = anchor;
+        }
+        if let Some(offset) = self.offset {
+            config.windows.stats.offset = [offset.x, offset.y];
+        }
+        if let Some(path) = &self.dragontail {
+            config.dragontail.path = Some(path.clone());
+        }
+        if let Some(path) = &self.icons {
+            config.icons.path = Some(path.clone());
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,824 @@
+use crate::cli::Args;
+use crate::hotkey::{self, HotkeyAction, HotkeyConfig};
+use crate::icons::IconsConfig;
+use crate::panels::PanelKind;
+use crate::placement::{Anchor, Placement};
+use crate::stat_rows::CompiledPanel;
+use crate::visibility::VisibilityConfig;
+use anyhow::{Context, Result, bail};
+use eframe::egui::{Color32, FontFamily, vec2};
+use league_overlay::dragontail::DragontailConfig;
+use league_overlay::fetcher::FetcherConfig;
+use league_overlay::live_client::LiveClientConfig;
+use league_overlay::polling::PollConfig;
+use notify::{RecommendedWatcher, RecursiveMode, Watcher};
+use serde::de::{self, Deserializer};
+use serde::{Deserialize, Serialize, Serializer};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::time::Duration;
+use tokio::sync::watch;
+
+/*
+Settings that used to be hard-coded, read from config.toml in the user config directory
+(%APPDATA%\league_overlay\config.toml on Windows, ~/.config/league_overlay/config.toml on Linux)
+or wherever --config points. A default one is written the first time so there's something to edit.
+Every section and key is optional, anything left out keeps its default.
+The file is watched while the overlay runs: a good edit is sent to everyone holding a ConfigReceiver,
+a broken one is reported and the last good config stays in use.
+Command line flags (--poll, --anchor, --ca-cert, ...) win over the file, also after a reload.
+A file from an older version (see CONFIG_VERSION) is brought up to date once when it's read, and
+saved back so it doesn't have to be again.
+*/
+
+//Bumped whenever a default changes in a way older files have to be updated for (see migrate)
+const CONFIG_VERSION: u32 = 1;
+
+//The stats window's default before it had the item strip, version 0 files may still have it
+const OLD_STATS_SIZE: [f32; 2] = [461.5, 234.0];
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    //Which defaults the file was written against, files from before there was one are version 0
+    #[serde(default)]
+    pub version: u32,
+    pub windows: WindowsConfig,
+    pub panel: PanelConfig,
+    pub hotkeys: HotkeyConfig,
+    pub poll: PollConfig,
+    pub live_client: LiveClientConfig,
+    pub visibility: VisibilityConfig,
+    pub dragontail: DragontailConfig,
+    pub icons: IconsConfig,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            version: CONFIG_VERSION,
+            windows: WindowsConfig::default(),
+            panel: PanelConfig::default(),
+            hotkeys: HotkeyConfig::default(),
+            poll: PollConfig::default(),
+            live_client: LiveClientConfig::default(),
+            visibility: VisibilityConfig::default(),
+            dragontail: DragontailConfig::default(),
+            icons: IconsConfig::default(),
+        }
+    }
+}
+
+/// One window per panel, [windows.stats], [windows.enemies], ...
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct WindowsConfig {
+    pub stats: WindowConfig,
+    pub enemies: WindowConfig,
+    pub objectives: WindowConfig,
+    pub toasts: WindowConfig,
+    pub scoreboard: WindowConfig,
+}
+
+impl Default for WindowsConfig {
+    fn default() -> Self {
+        let placement = Placement::default();
+        Self {
+            // Where the overlay has always been, the rest start hidden
+            stats: WindowConfig {
+                anchor: placement.anchor,
+                offset: [placement.offset.x, placement.offset.y],
+                // 355x195 scaled by 1.3 and 1.2 as it always was, plus room for the items
+                size: [461.5, 266.0],
+                visible: true,
+                hotkey: String::new(),
+            },
+            enemies: WindowConfig::hidden(Anchor::Right, [6.5, 0.0], [320.0, 200.0]),
+            objectives: WindowConfig::hidden(Anchor::TopLeft, [20.0, 200.0], [220.0, 150.0]),
+            toasts: WindowConfig::hidden(Anchor::Top, [0.0, 80.0], [420.0, 140.0]),
+            scoreboard: WindowConfig::hidden(Anchor::Top, [0.0, 6.0], [360.0, 70.0]),
+        }
+    }
+}
+
+impl WindowsConfig {
+    pub fn get(&self, kind: PanelKind) -> &WindowConfig {
+        match kind {
+            PanelKind::Stats => &self.stats,
+            PanelKind::Enemies => &self.enemies,
+            PanelKind::Objectives => &self.objectives,
+            PanelKind::Toasts => &self.toasts,
+            PanelKind::Scoreboard => &self.scoreboard,
+        }
+    }
+
+    pub fn get_mut(&mut self, kind: PanelKind) -> &mut WindowConfig {
+        match kind {
+            PanelKind::Stats => &mut self.stats,
+            PanelKind::Enemies => &mut self.enemies,
+            PanelKind::Objectives => &mut self.objectives,
+            PanelKind::Toasts => &mut self.toasts,
+            PanelKind::Scoreboard => &mut self.scoreboard,
+        }
+    }
+}
+
+// Each panel has its own defaults, so a section only changes the keys it has
+impl<'de> Deserialize<'de> for WindowsConfig {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        #[derive(Deserialize)]
+        #[serde(deny_unknown_fields)]
+        struct WindowPatch {
+            anchor: Option<Anchor>,
+            offset: Option<[f32; 2]>,
+            size: Option<[f32; 2]>,
+            visible: Option<bool>,
+            hotkey: Option<String>,
+        }
+
+        let mut windows = WindowsConfig::default();
+        for (name, patch) in BTreeMap::<String, WindowPatch>::deserialize(deserializer)? {
+            let kind = PanelKind::ALL
+                .into_iter()
+                .find(|kind| kind.name() == name)
+                .ok_or_else(|| {
+                    de::Error::custom(format!(
+                        "unknown panel {:?} (stats, enemies, objectives, toasts, scoreboard)",
+                        name
+                    ))
+                })?;
+            let window = windows.get_mut(kind);
+            if let Some(anchor) = patch.anchor {
+                window.anchor = anchor;
+            }
+            if let Some(offset) = patch.offset {
+                window.offset = offset;
+            }
+            if let Some(size) = patch.size {
+                window.size = size;
+            }
+            if let Some(visible) = patch.visible {
+                window.visible = visible;
+            }
+            if let Some(hotkey) = patch.hotkey {
+                window.hotkey = hotkey;
+            }
+        }
+        Ok(windows)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct WindowConfig {
+    pub anchor: Anchor,
+    //Points in from the anchored edges
+    pub offset: [f32; 2],
+    //Width and height in points
+    pub size: [f32; 2],
+    //Shown when the overlay starts
+    pub visible: bool,
+    //Shows/hides just this window, empty for none
+    pub hotkey: String,
+}
+
+impl WindowConfig {
+    fn hidden(anchor: Anchor, offset: [f32; 2], size: [f32; 2]) -> Self {
+        Self {
+            anchor,
+            offset,
+            size,
+            visible: false,
+            hotkey: String::new(),
+        }
+    }
+
+    pub fn placement(&self) -> Placement {
+        Placement {
+            anchor: self.anchor,
+            offset: vec2(self.offset[0], self.offset[1]),
+        }
+    }
+
+    pub fn size(&self) -> eframe::egui::Vec2 {
+        vec2(self.size[0], self.size[1])
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct PanelConfig {
+    pub title: String,
+    pub title_color: HexColor,
+    pub label_color: HexColor,
+    pub value_color: HexColor,
+    pub font: Font,
+    //Body text in points, the title is a bit bigger
+    pub font_size: f32,
+    //How dark the column backgrounds are, 0 (see-through) to 1
+    pub opacity: f64,
+    //Your items under the stats (see inventory.rs)
+    pub inventory: bool,
+    //Named expressions rows can use like any other variable (see stat_rows.rs)
+    pub vars: BTreeMap<String, String>,
+    pub left: Vec<RowConfig>,
+    pub right: Vec<RowConfig>,
+}
+
+impl Default for PanelConfig {
+    fn default() -> Self {
+        Self {
+            title: "Do Not Tilt UwU".to_string(),
+            title_color: HexColor(Color32::MAGENTA),
+            label_color: HexColor(Color32::MAGENTA),
+            value_color: HexColor(Color32::GRAY),
+            font: Font::Monospace,
+            font_size: 15.0,
+            opacity: 0.31,
+            inventory: true,
+            vars: BTreeMap::from([
+                (
+                    "armor_pen_pct".to_string(),
+                    "100 * (1 - armor_pen)".to_string(),
+                ),
+                (
+                    "magic_pen_flat".to_string(),
+                    "magic_lethality + magic_pen".to_string(),
+                ),
+                (
+                    "magic_pen_pct".to_string(),
+                    "100 * (1 - magic_pen_percent)".to_string(),
+                ),
+            ]),
+            left: vec![
+                RowConfig::new("Attack Damage:", "⚔", "{attack_damage:.0}", (255, 100, 100))
+                    .with_image("attack_damage"),
+                RowConfig::new(
+                    "Ability Power:",
+                    "✨",
+                    "{ability_power:.0}",
+                    (100, 150, 255),
+                )
+                .with_image("ability_power"),
+                RowConfig::new("Armor:", "🛡", "{armor:.0}", (200, 200, 100)).with_image("armor"),
+                RowConfig::new("Magic Resist:", "🔮", "{magic_resist:.0}", (150, 100, 255))
+                    .with_image("magic_resist"),
+                RowConfig::new("CS/min:", "🗡", "{cs_per_min:.1}", (255, 215, 0))
+                    .with_image("cs_per_min")
+                    // On pace is 8 a minute, give the first few minutes a pass
+                    .with_rule("minutes < 3", None)
+                    .with_rule("cs_per_min < 6", Some((255, 80, 80)))
+                    .with_rule("cs_per_min < 8", Some((255, 215, 0)))
+                    .with_rule("true", Some((100, 255, 100))),
+                RowConfig::new("Move Speed: ", "💨", "{move_speed:.0}", (100, 255, 100))
+                    .with_image("move_speed"),
+                RowConfig::new("Crit Chance:", "💥", "{crit_chance:.0}%", (255, 165, 0))
+                    .with_image("crit_chance"),
+            ],
+            right: vec![
+                RowConfig::new(
+                    "Lethality:",
+                    "",
+                    "{lethality:.0} | %{armor_pen_pct:.2}",
+                    (255, 80, 80),
+                )
+                .with_image("lethality")
+                .or_else(
+                    "!((lethality == 0 || physical_lethality == 0) && armor_pen == 1)",
+                    "Farm!!!",
+                ),
+                RowConfig::new(
+                    "Mag Pen:",
+                    "",
+                    "{magic_pen_flat:.0} | %{magic_pen_pct:.2}",
+                    (128, 0, 128),
+                )
+                .with_image("magic_pen")
+                .or_else(
+                    "magic_pen_flat != 0 || magic_pen_percent != 1",
+                    "Keep it up!",
+                ),
+                RowConfig::new("Att. Sp.:", "", "{attack_speed:.2} atk/s", (255, 255, 100))
+                    .with_image("attack_speed"),
+                RowConfig::new(
+                    "HP Regen:",
+                    "",
+                    "{health_regen_rate:.1} hp/s",
+                    (100, 255, 100),
+                )
+                .with_image("health_regen")
+                .with_flash("current_health < 0.3 * max_health", (255, 80, 80)),
+                RowConfig::new("Life Steal:", "❤", "{life_steal:.0}%", (255, 100, 100))
+                    .with_image("life_steal"),
+                RowConfig::new("Total Gold:", "💰", "{total_gold:.0}", (255, 215, 0))
+                    .with_image("gold"),
+            ],
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Font {
+    Monospace,
+    Proportional,
+}
+
+impl Font {
+    pub const ALL: [Font; 2] = [Font::Monospace, Font::Proportional];
+
+    pub fn family(&self) -> FontFamily {
+        match self {
+            Font::Monospace => FontFamily::Monospace,
+            Font::Proportional => FontFamily::Proportional,
+        }
+    }
+}
+
+/// One line of a stats column
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct RowConfig {
+    pub label: String,
+    #[serde(default)]
+    pub icon: String,
+    //Drawn instead of the icon when <[icons] path>/stats/<image>.png is there (see icons.rs)
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub image: String,
+    //Icon color
+    pub color: HexColor,
+    //Template, e.g. "{attack_speed:.2} atk/s" (see expr.rs)
+    pub value: String,
+    //Expression, `otherwise` is shown instead of the value while it's false
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub when: Option<String>,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub otherwise: String,
+    #[serde(default = "visible_by_default")]
+    pub visible: bool,
+    //Checked in order, the first one that holds decides how the value looks
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub rules: Vec<ColorRule>,
+}
+
+/// e.g. { when = "cs_per_min < 6", color = "#ff5050" } or { when = "current_health < 0.3 * max_health", flash = true }
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ColorRule {
+    pub when: String,
+    //Value color while it holds, the panel's value_color if left out
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub color: Option<HexColor>,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub flash: bool,
+}
+
+fn visible_by_default() -> bool {
+    true
+}
+
+impl RowConfig {
+    pub fn new(label: &str, icon: &str, value: &str, (r, g, b): (u8, u8, u8)) -> Self {
+        Self {
+            label: label.to_string(),
+            icon: icon.to_string(),
+            image: String::new(),
+            color: HexColor(Color32::from_rgb(r, g, b)),
+            value: value.to_string(),
+            when: None,
+            otherwise: String::new(),
+            visible: true,
+            rules: Vec::new(),
+        }
+    }
+
+    fn with_image(mut self, image: &str) -> Self {
+        self.image = image.to_string();
+        self
+    }
+
+    fn with_rule(mut self, when: &str, color: Option<(u8, u8, u8)>) -> Self {
+        self.rules.push(ColorRule {
+            when: when.to_string(),
+            color: color.map(|(r, g, b)| HexColor(Color32::from_rgb(r, g, b))),
+            flash: false,
+        });
+        self
+    }
+
+    fn with_flash(mut self, when: &str, (r, g, b): (u8, u8, u8)) -> Self {
+        self.rules.push(ColorRule {
+            when: when.to_string(),
+            color: Some(HexColor(Color32::from_rgb(r, g, b))),
+            flash: true,
+        });
+        self
+    }
+
+    fn or_else(mut self, when: &str, otherwise: &str) -> Self {
+        self.when = Some(when.to_string());
+        self.otherwise = otherwise.to_string();
+        self
+    }
+}
+
+/// "#rrggbb" or "#rrggbbaa" in the file
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct HexColor(pub Color32);
+
+impl FromStr for HexColor {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let hex = s
+            .strip_prefix('#')
+            .with_context(|| format!("Colors look like \"#ff8800\", got {:?}", s))?;
+        let channel = |i: usize| {
+            hex.get(i..i + 2)
+                .and_then(|c| u8::from_str_radix(c, 16).ok())
+                .with_context(|| format!("{:?} isn't a hex color", s))
+        };
+        let color = match hex.len() {
+            6 => Color32::from_rgb(channel(0)?, channel(2)?, channel(4)?),
+            8 => {
+                Color32::from_rgba_unmultiplied(channel(0)?, channel(2)?, channel(4)?, channel(6)?)
+            }
+            _ => bail!("Colors look like \"#ff8800\" or \"#ff8800cc\", got {:?}", s),
+        };
+        Ok(HexColor(color))
+    }
+}
+
+impl Serialize for HexColor {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let [r, g, b, a] = self.0.to_srgba_unmultiplied();
+        let hex = if a == 255 {
+            format!("#{:02x}{:02x}{:02x}", r, g, b)
+        } else {
+            format!("#{:02x}{:02x}{:02x}{:02x}", r, g, b, a)
+        };
+        serializer.serialize_str(&hex)
+    }
+}
+
+impl<'de> Deserialize<'de> for HexColor {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        String::deserialize(deserializer)?
+            .parse()
+            .map_err(de::Error::custom)
+    }
+}
+
+impl Config {
+    /// The [live_client] and [poll] sections, all the fetcher goes by
+    /// Brings a config read from an older file up to date, false if it already was
+    pub fn migrate(&mut self) -> bool {
+        if self.version >= CONFIG_VERSION {
+            return false;
+        }
+        // 0 -> 1: the stats window got taller for the item strip, left at the old default it
+        // would cut the items off
+        if self.version < 1 && self.windows.stats.size == OLD_STATS_SIZE {
+            self.windows.stats.size = WindowsConfig::default().stats.size;
+        }
+        self.version = CONFIG_VERSION;
+        true
+    }
+
+    pub fn fetcher(&self) -> FetcherConfig {
+        FetcherConfig {
+            live_client: self.live_client.clone(),
+            poll: self.poll.clone(),
+        }
+    }
+
+    /// [hotkeys] plus each window's own hotkey, empty ones left out
+    pub fn hotkey_bindings(&self) -> Vec<(HotkeyAction, String)> {
+        let actions = HotkeyAction::GLOBAL
+            .into_iter()
+            .map(|action| (action, self.hotkeys.binding(action)));
+        let windows = PanelKind::ALL.into_iter().map(|kind| {
+            (
+                HotkeyAction::TogglePanel(kind),
+                self.windows.get(kind).hotkey.as_str(),
+            )
+        });
+        actions
+            .chain(windows)
+            .filter(|(_, binding)| !binding.trim().is_empty())
+            .map(|(action, binding)| (action, binding.to_string()))
+            .collect()
+    }
+
+    /// Everything wrong with the config that serde can't catch, empty if it's usable
+    pub fn problems(&self) -> Vec<String> {
+        let mut problems: Vec<String> = self
+            .poll
+            .problems()
+            .into_iter()
+            .map(|problem| format!("poll.{}", problem))
+            .collect();
+        problems.extend(
+            self.visibility
+                .problems()
+                .into_iter()
+                .map(|problem| format!("visibility.{}", problem)),
+        );
+
+        for kind in PanelKind::ALL {
+            let window = self.windows.get(kind);
+            if window.size.iter().any(|side| side.is_nan() || *side <= 0.0) {
+                problems.push(format!(
+                    "windows.{}.size has to be bigger than 0",
+                    kind.name()
+                ));
+            }
+        }
+        if let Err(e) = CompiledPanel::compile(&self.panel) {
+            problems.push(format!("panel: {:#}", e));
+        }
+        if !(6.0..=48.0).contains(&self.panel.font_size) {
+            problems.push("panel.font_size has to be between 6 and 48".to_string());
+        }
+        if !(0.0..=1.0).contains(&self.panel.opacity) {
+            problems.push("panel.opacity has to be between 0 and 1".to_string());
+        }
+        problems.extend(hotkey::problems(&self.hotkey_bindings()));
+        // The game only serves HTTPS, plain http would skip the certificate checks in tls.rs
+        if !self.live_client.url.starts_with("https://") {
+            problems.push(format!(
+                "live_client.url has to start with https:// (got {:?})",
+                self.live_client.url
+            ));
+        }
+        if self.live_client.ca_cert.is_some() && self.live_client.insecure {
+            problems
+                .push("live_client.ca_cert and live_client.insecure can't both be set".to_string());
+        }
+        problems
+    }
+}
+
+/// Where the config lives unless --config says otherwise, None if the OS has no config directory
+pub fn default_path() -> Option<PathBuf> {
+    dirs::config_dir().map(|dir| dir.join("league_overlay").join("config.toml"))
+}
+
+/// Reads and validates the config, then lays the command line on top
+pub fn load(path: &Path, args: &Args) -> Result<Config> {
+    let mut config = read(path)?;
+    args.apply_overrides(&mut config)?;
+    Ok(config)
+}
+
+/// Just what's in the file, for the settings menu to edit without baking in the command line
+pub fn read(path: &Path) -> Result<Config> {
+    let text = std::fs::read_to_string(path)
+        .with_context(|| format!("Failed to read {}", path.display()))?;
+    let mut config: Config = toml::from_str(&text)
+        .with_context(|| format!("{} is not a valid config", path.display()))?;
+    let migrated = config.migrate();
+
+    let problems = config.problems();
+    if !problems.is_empty() {
+        bail!(
+            "{} has problems:\n  - {}",
+            path.display(),
+            problems.join("\n  - ")
+        );
+    }
+    // Only once it's known to be good, and the overlay works with it even if writing fails
+    if migrated {
+        match save(path, &config) {
+            Ok(()) => println!(
+                "Updated {} to config version {}",
+                path.display(),
+                CONFIG_VERSION
+            ),
+            Err(e) => println!("Failed to save the updated config: {:#}", e),
+        }
+    }
+    Ok(config)
+}
+
+/// Writes the default config if there isn't one yet
+pub fn create_default(path: &Path) -> Result<()> {
+    if path.exists() {
+        return Ok(());
+    }
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)
+            .with_context(|| format!("Failed to create {}", dir.display()))?;
+    }
+    save(path, &Config::default())?;
+    println!("Wrote the default config to {}", path.display());
+    Ok(())
+}
+
+pub fn save(path: &Path, config: &Config) -> Result<()> {
+    let text = format!(
+        "# League Overlay settings, changes are picked up while the overlay is running\n\n{}",
+        toml::to_string_pretty(config)?
+    );
+    std::fs::write(path, text).with_context(|| format!("Failed to write {}", path.display()))
+}
+
+/// The config in use, and what was wrong with the file if the last edit didn't load
+#[derive(Debug, Clone, PartialEq)]
+pub struct LoadedConfig {
+    pub config: Config,
+    pub error: Option<String>,
+}
+
+pub type ConfigReceiver = watch::Receiver<LoadedConfig>;
+
+/// The fetcher's part of the config, updated only when one of its sections changes
+pub fn fetcher_config(mut config: ConfigReceiver) -> watch::Receiver<FetcherConfig> {
+    let (sender, receiver) = watch::channel(config.borrow_and_update().config.fetcher());
+    tokio::spawn(async move {
+        while config.changed().await.is_ok() {
+            let fetcher = config.borrow_and_update().config.fetcher();
+            sender.send_if_modified(|current| {
+                let changed = *current != fetcher;
+                *current = fetcher;
+                changed
+            });
+            if sender.is_closed() {
+                break;
+            }
+        }
+    });
+    receiver
+}
+
+/// Reloads the config whenever the file changes. The watcher stops when it's dropped
+pub fn watch(
+    path: PathBuf,
+    args: Args,
+    sender: watch::Sender<LoadedConfig>,
+) -> Result<RecommendedWatcher> {
+    let (events_sender, events) = std::sync::mpsc::channel::<notify::Result<notify::Event>>();
+    let mut watcher = notify::recommended_watcher(events_sender)?;
+    // Editors like to save by replacing the file, so watch the folder rather than the file
+    let dir = path
+        .parent()
+        .with_context(|| format!("{} has no parent directory", path.display()))?;
+    watcher.watch(dir, RecursiveMode::NonRecursive)?;
+
+    std::thread::spawn(move || {
+        while let Ok(event) = events.recv() {
+            let touches_config = event.is_ok_and(|event| {
+                (event.kind.is_modify() || event.kind.is_create())
+                    && event
+                        .paths
+                        .iter()
+                        .any(|changed| changed.file_name() == path.file_name())
+            });
+            if !touches_config {
+                continue;
+            }
+
+            // A save often comes as a few events in a row, let them settle before reading
+            std::thread::sleep(Duration::from_millis(150));
+            while events.try_recv().is_ok() {}
+
+            let loaded = match load(&path, &args) {
+                Ok(config) => LoadedConfig {
+                    config,
+                    error: None,
+                },
+                Err(e) => {
+                    println!("Config not reloaded: {:#}", e);
+                    LoadedConfig {
+                        config: sender.borrow().config.clone(),
+                        error: Some(format!("{:#}", e)),
+                    }
+                }
+            };
+            sender.send_if_modified(|current| {
+                if *current == loaded {
+                    return false;
+                }
+                if loaded.error.is_none() {
+                    println!("Reloaded {}", path.display());
+                }
+                *current = loaded;
+                true
+            });
+        }
+    });
+
+    Ok(watcher)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Waits for the watcher to send something new, the edit has to settle first (see watch)
+    fn next_reload(receiver: &mut ConfigReceiver) -> LoadedConfig {
+        for _ in 0..100 {
+            if receiver.has_changed().unwrap() {
+                return receiver.borrow_and_update().clone();
+            }
+            std::thread::sleep(Duration::from_millis(50));
+        }
+        panic!("the config was never reloaded");
+    }
+
+    #[test]
+    fn the_default_config_is_written_once_and_reads_back() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("league_overlay").join("config.toml");
+        create_default(&path).unwrap();
+        assert_eq!(read(&path).unwrap(), Config::default());
+
+        std::fs::write(&path, "[panel]\ntitle = \"Mine\"\n").unwrap();
+        create_default(&path).unwrap();
+        assert_eq!(read(&path).unwrap().panel.title, "Mine");
+    }
+
+    #[test]
+    fn plain_http_is_not_allowed() {
+        let config: Config =
+            toml::from_str("[live_client]\nurl = \"http://127.0.0.1:2999\"\n").unwrap();
+        let problems = config.problems();
+        assert_eq!(problems.len(), 1);
+        assert!(problems[0].starts_with("live_client.url has to start with https://"));
+        assert!(Config::default().problems().is_empty());
+    }
+
+    #[test]
+    fn a_bad_edit_keeps_the_last_good_config_and_says_why() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("config.toml");
+        create_default(&path).unwrap();
+        let (sender, mut receiver) = watch::channel(LoadedConfig {
+            config: read(&path).unwrap(),
+            error: None,
+        });
+        let _watcher = watch(path.clone(), Args::default(), sender).unwrap();
+
+        std::fs::write(&path, "version = 1\n[panel]\ntitle = \"Edited\"\n").unwrap();
+        let good = next_reload(&mut receiver);
+        assert_eq!(good.error, None);
+        assert_eq!(good.config.panel.title, "Edited");
+
+        std::fs::write(
+            &path,
+            "version = 1\n[live_client]\nurl = \"http://127.0.0.1:2999\"\n",
+        )
+        .unwrap();
+        let bad = next_reload(&mut receiver);
+        assert_eq!(bad.config, good.config);
+        assert!(
+            bad.error
+                .unwrap()
+                .contains("live_client.url has to start with https://")
+        );
+
+        std::fs::write(&path, "version = 1\n[panel\n").unwrap();
+        let broken = next_reload(&mut receiver);
+        assert_eq!(broken.config, good.config);
+        assert!(broken.error.unwrap().contains("is not a valid config"));
+    }
+
+    #[test]
+    fn the_old_default_stats_size_is_moved_to_the_new_one_once() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("config.toml");
+        std::fs::write(&path, "[windows.stats]\nsize = [461.5, 234.0]\n").unwrap();
+
+        let config = read(&path).unwrap();
+        assert_eq!(config.version, CONFIG_VERSION);
+        assert_eq!(
+            config.windows.stats.size,
+            WindowsConfig::default().stats.size
+        );
+        // Saved back, so the file is up to date from now on
+        let saved: Config = toml::from_str(&std::fs::read_to_string(&path).unwrap()).unwrap();
+        assert_eq!(saved, config);
+    }
+
+    #[test]
+    fn sizes_are_kept_as_they_are_once_migrated() {
+        let mut config: Config =
+            toml::from_str("[windows.stats]\nsize = [400.0, 234.0]\n").unwrap();
+        assert!(config.migrate());
+        assert_eq!(config.windows.stats.size, [400.0, 234.0]);
+
+        // Picked on purpose after the version that moved it
+        let mut config: Config =
+            toml::from_str("version = 1\n[windows.stats]\nsize = [461.5, 234.0]\n").unwrap();
+        assert!(!config.migrate());
+        assert_eq!(config.windows.stats.size, OLD_STATS_SIZE);
+    }
+}
@@ -0,0 +1,191 @@
+use crate::live_client::LiveClientError;
+use crate::polling::Endpoint;
+use std::time::{Duration, Instant};
+
+/*
+Where we are with the League client. The fetcher works this out and sends it to the overlay
+so it can show more than "Waiting for game data...".
+    NoClient -> Loading -> InGame (or Spectating) -> PostGame -> NoClient
+/gamestats answers 404 through the loading screen, so a 404 there only means the endpoint is gone
+(EndpointMissing) once it has gone on for longer than any loading screen. Any other endpoint
+answering 404 while /gamestats works is gone straight away.
+*/
+
+//Loading screens take a couple of minutes at worst
+const NOT_FOUND_GRACE: Duration = Duration::from_secs(5 * 60);
+
+/// What the overlay can tell about the League client
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConnectionState {
+    /// Nothing listening on the Live Client port
+    NoClient,
+    /// The API is up but the game isn't (loading screen)
+    Loading,
+    /// In a game with an active player
+    InGame,
+    /// In a game without an active player
+    Spectating,
+    /// GameEnd has been sent, the client sticks around until the stats screen
+    PostGame,
+    /// An endpoint keeps answering 404, most likely Riot moved or renamed it
+    EndpointMissing,
+}
+
+impl ConnectionState {
+    /// What a failed /gamestats request says about the client, None if it doesn't tell us anything
+    pub fn from_error(error: &LiveClientError) -> Option<Self> {
+        match error {
+            LiveClientError::ConnectionRefused | LiveClientError::Timeout { .. } => {
+                Some(ConnectionState::NoClient)
+            }
+            LiveClientError::HttpStatus { .. } => Some(ConnectionState::Loading),
+            _ => None,
+        }
+    }
+
+    /// A few words for the overlay to show
+    pub fn description(&self) -> &'static str {
+        match self {
+            ConnectionState::NoClient => "League isn't running",
+            ConnectionState::Loading => "Loading into game...",
+            ConnectionState::InGame => "In game",
+            ConnectionState::Spectating => "Spectating (no active player)",
+            ConnectionState::PostGame => "Game over",
+            ConnectionState::EndpointMissing => {
+                "Live Client endpoint not found (has the API changed?)"
+            }
+        }
+    }
+}
+
+/// What the fetcher last found out about the client
+#[derive(Debug, Clone, PartialEq)]
+pub struct ConnectionStatus {
+    /// Where we are
+    pub state: ConnectionState,
+    /// Last thing that went wrong, cleared once a request goes through
+    pub error: Option<String>,
+}
+
+/// Keeps the last status so only changes get sent
+#[derive(Default)]
+pub struct ConnectionTracker {
+    status: Option<ConnectionStatus>,
+    //When /gamestats started answering 404, cleared by anything else
+    not_found_since: Option<Instant>,
+}
+
+impl ConnectionTracker {
+    /// Nothing known yet
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The current state, None before anything has been tried
+    pub fn state(&self) -> Option<ConnectionState> {
+        self.status.as_ref().map(|status| status.state)
+    }
+
+    /// Like ConnectionState::from_error, but tells a 404 that won't go away from a loading screen
+    pub fn state_for_error(&mut self, error: &LiveClientError) -> Option<ConnectionState> {
+        let LiveClientError::HttpStatus {
+            endpoint,
+            status: 404,
+        } = error
+        else {
+            self.not_found_since = None;
+            return ConnectionState::from_error(error);
+        };
+        if endpoint != Endpoint::GameStats.path() {
+            return Some(ConnectionState::EndpointMissing);
+        }
+        let since = *self.not_found_since.get_or_insert_with(Instant::now);
+        if since.elapsed() > NOT_FOUND_GRACE {
+            Some(ConnectionState::EndpointMissing)
+        } else {
+            Some(ConnectionState::Loading)
+        }
+    }
+
+    /// Moves to `state`, returns the new status if anything changed
+    pub fn update(
+        &mut self,
+        state: ConnectionState,
+        error: Option<&LiveClientError>,
+    ) -> Option<ConnectionStatus> {
+        // The game keeps answering after GameEnd, that doesn't mean we're back in it
+        let state = match (self.state(), state) {
+            (Some(ConnectionState::PostGame), ConnectionState::InGame) => ConnectionState::PostGame,
+            _ => state,
+        };
+
+        if error.is_none() {
+            self.not_found_since = None;
+        }
+
+        let status = ConnectionStatus {
+            state,
+            error: error.map(|e| e.to_string()),
+        };
+        if self.status.as_ref() == Some(&status) {
+            return None;
+        }
+        if self.state() != Some(state) {
+            log::info!("Connection state: {:?}", state);
+        }
+        self.status = Some(status.clone());
+        Some(status)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn not_found(endpoint: Endpoint) -> LiveClientError {
+        LiveClientError::HttpStatus {
+            endpoint: endpoint.path().to_string(),
+            status: 404,
+        }
+    }
+
+    #[test]
+    fn gamestats_404_is_loading_until_it_has_gone_on_too_long() {
+        let mut tracker = ConnectionTracker::new();
+        let error = not_found(Endpoint::GameStats);
+        assert_eq!(
+            tracker.state_for_error(&error),
+            Some(ConnectionState::Loading)
+        );
+
+        tracker.not_found_since = Some(Instant::now() - NOT_FOUND_GRACE - Duration::from_secs(1));
+        assert_eq!(
+            tracker.state_for_error(&error),
+            Some(ConnectionState::EndpointMissing)
+        );
+
+        // Getting through starts the wait over
+        tracker.update(ConnectionState::InGame, None);
+        assert_eq!(
+            tracker.state_for_error(&error),
+            Some(ConnectionState::Loading)
+        );
+    }
+
+    #[test]
+    fn other_404s_are_missing_endpoints_straight_away() {
+        let mut tracker = ConnectionTracker::new();
+        assert_eq!(
+            tracker.state_for_error(&not_found(Endpoint::PlayerList)),
+            Some(ConnectionState::EndpointMissing)
+        );
+        let server_error = LiveClientError::HttpStatus {
+            endpoint: Endpoint::GameStats.path().to_string(),
+            status: 500,
+        };
+        assert_eq!(
+            tracker.state_for_error(&server_error),
+            Some(ConnectionState::Loading)
+        );
+    }
+}
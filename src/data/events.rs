@@ -1,16 +1,458 @@
-use serde::Deserialize;
+use serde::de::{self, Deserializer};
+use serde::ser::{self, Serializer};
+use serde::{Deserialize, Serialize};
 
-#[derive(Deserialize, Debug)]
-#[serde(rename_all = "PascalCase")]
+/*
+Events from /eventdata (and the events block of /allgamedata).
+Every event has EventID, EventName and EventTime, the rest depends on EventName
+so the payload is split into the shared header and a tagged EventKind.
+Anything we don't know about ends up in EventKind::Unknown with the raw json kept around.
+*/
+
+/// One entry of the event log
+#[derive(Debug, Clone)]
 pub struct Event {
-    #[serde(rename = "EventID")]
+    /// Counts up from 0 in each game
     pub event_id: u32,
-    pub event_name: String,
+    /// Game time in seconds
     pub event_time: f64,
+    /// What happened
+    pub kind: EventKind,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq)]
+/// What happened, by EventName
+#[serde(tag = "EventName")]
+pub enum EventKind {
+    /// The game has started
+    GameStart,
+    /// The first minions are on their way
+    MinionsSpawning,
+    /// The first turret of the game fell
+    FirstBrick {
+        /// Who got the last hit, a summoner name or a minion/turret name
+        #[serde(rename = "KillerName")]
+        killer: String,
+    },
+    /// The first champion kill of the game
+    FirstBlood {
+        /// Who got it
+        #[serde(rename = "Recipient")]
+        recipient: String,
+    },
+    /// A turret fell
+    TurretKilled {
+        /// Which turret, "Turret_T2_L_03_A"
+        #[serde(rename = "TurretKilled")]
+        turret: String,
+        /// Who got the last hit, a summoner name or a minion/turret name
+        #[serde(rename = "KillerName")]
+        killer: String,
+        /// Everyone else who helped
+        #[serde(rename = "Assisters", default)]
+        assisters: Vec<String>,
+    },
+    /// An inhibitor fell
+    InhibKilled {
+        /// Which inhibitor, "Barracks_T1_L1"
+        #[serde(rename = "InhibKilled")]
+        inhib: String,
+        /// Who got the last hit, a summoner name or a minion/turret name
+        #[serde(rename = "KillerName")]
+        killer: String,
+        /// Everyone else who helped
+        #[serde(rename = "Assisters", default)]
+        assisters: Vec<String>,
+    },
+    /// An inhibitor comes back soon
+    InhibRespawningSoon {
+        /// Which inhibitor, "Barracks_T1_L1"
+        #[serde(rename = "InhibRespawningSoon")]
+        inhib: String,
+    },
+    /// An inhibitor is back
+    InhibRespawned {
+        /// Which inhibitor, "Barracks_T1_L1"
+        #[serde(rename = "InhibRespawned")]
+        inhib: String,
+    },
+    /// A dragon was taken
+    DragonKill {
+        /// Which dragon
+        #[serde(rename = "DragonType")]
+        dragon_type: DragonType,
+        /// Taken by the team that wasn't fighting it
+        #[serde(rename = "Stolen", with = "bool_string")]
+        stolen: bool,
+        /// Who got the last hit, a summoner name or a minion/turret name
+        #[serde(rename = "KillerName")]
+        killer: String,
+        /// Everyone else who helped
+        #[serde(rename = "Assisters", default)]
+        assisters: Vec<String>,
+    },
+    /// Rift Herald was taken
+    HeraldKill {
+        /// Taken by the team that wasn't fighting it
+        #[serde(rename = "Stolen", with = "bool_string")]
+        stolen: bool,
+        /// Who got the last hit, a summoner name or a minion/turret name
+        #[serde(rename = "KillerName")]
+        killer: String,
+        /// Everyone else who helped
+        #[serde(rename = "Assisters", default)]
+        assisters: Vec<String>,
+    },
+    /// Void grubs
+    HordeKill {
+        /// Taken by the team that wasn't fighting it
+        #[serde(rename = "Stolen", with = "bool_string")]
+        stolen: bool,
+        /// Who got the last hit, a summoner name or a minion/turret name
+        #[serde(rename = "KillerName")]
+        killer: String,
+        /// Everyone else who helped
+        #[serde(rename = "Assisters", default)]
+        assisters: Vec<String>,
+    },
+    /// Baron Nashor was taken
+    BaronKill {
+        /// Taken by the team that wasn't fighting it
+        #[serde(rename = "Stolen", with = "bool_string")]
+        stolen: bool,
+        /// Who got the last hit, a summoner name or a minion/turret name
+        #[serde(rename = "KillerName")]
+        killer: String,
+        /// Everyone else who helped
+        #[serde(rename = "Assisters", default)]
+        assisters: Vec<String>,
+    },
+    /// Atakhan was taken
+    AtakhanKill {
+        /// Taken by the team that wasn't fighting it
+        #[serde(rename = "Stolen", with = "bool_string")]
+        stolen: bool,
+        /// Who got the last hit, a summoner name or a minion/turret name
+        #[serde(rename = "KillerName")]
+        killer: String,
+        /// Everyone else who helped
+        #[serde(rename = "Assisters", default)]
+        assisters: Vec<String>,
+    },
+    /// A champion died to another
+    ChampionKill {
+        /// Who got the last hit, a summoner name or a minion/turret name
+        #[serde(rename = "KillerName")]
+        killer: String,
+        /// Who died
+        #[serde(rename = "VictimName")]
+        victim: String,
+        /// Everyone else who helped
+        #[serde(rename = "Assisters", default)]
+        assisters: Vec<String>,
+    },
+    /// A double kill or better
+    Multikill {
+        /// Who got the last hit, a summoner name or a minion/turret name
+        #[serde(rename = "KillerName")]
+        killer: String,
+        /// 2 for a double kill up to 5 for a penta
+        #[serde(rename = "KillStreak")]
+        kill_streak: u8,
+    },
+    /// A whole team is dead
+    Ace {
+        /// Who got the last kill
+        #[serde(rename = "Acer")]
+        acer: String,
+        /// "ORDER" (blue) or "CHAOS" (red)
+        #[serde(rename = "AcingTeam")]
+        acing_team: String,
+    },
+    /// The game is over, sent to each client
+    GameEnd {
+        /// "Win" or "Lose", from this client's point of view
+        #[serde(rename = "Result")]
+        result: String,
+    },
+    /// Never produced (or written) by serde directly, see Event's Deserialize/Serialize impls
+    #[serde(skip)]
+    Unknown(serde_json::Value),
+}
+
+/// The dragons, by the name the Live Client gives them
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DragonType {
+    /// Infernal
+    Fire,
+    /// Mountain
+    Earth,
+    /// Ocean
+    Water,
+    /// Cloud
+    Air,
+    /// Hextech
+    Hextech,
+    /// Chemtech
+    Chemtech,
+    /// Elder, the one after a soul
+    Elder,
+    /// One added after this was written
+    #[serde(other)]
+    Other,
+}
+
+impl<'de> Deserialize<'de> for Event {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        #[derive(Deserialize)]
+        #[serde(rename_all = "PascalCase")]
+        struct Header {
+            #[serde(rename = "EventID")]
+            event_id: u32,
+            event_time: f64,
+        }
+
+        let raw = serde_json::Value::deserialize(deserializer)?;
+        let header = Header::deserialize(&raw).map_err(de::Error::custom)?;
+        // New event types (or changed fields on old ones) shouldn't kill the whole snapshot
+        let kind = EventKind::deserialize(&raw).unwrap_or(EventKind::Unknown(raw));
+
+        Ok(Event {
+            event_id: header.event_id,
+            event_time: header.event_time,
+            kind,
+        })
+    }
+}
+
+//Writes the event back out in the same shape Riot sends it
+impl Serialize for Event {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let mut value = match &self.kind {
+            EventKind::Unknown(raw) => raw.clone(),
+            kind => serde_json::to_value(kind).map_err(ser::Error::custom)?,
+        };
+        if let Some(fields) = value.as_object_mut() {
+            fields.insert("EventID".to_string(), self.event_id.into());
+            fields.insert("EventTime".to_string(), self.event_time.into());
+        }
+        value.serialize(serializer)
+    }
 }
 
-#[derive(Deserialize, Debug)]
-#[serde(rename_all = "PascalCase")]
+impl EventKind {
+    /// The EventName this kind was parsed from
+    pub fn name(&self) -> &str {
+        match self {
+            EventKind::GameStart => "GameStart",
+            EventKind::MinionsSpawning => "MinionsSpawning",
+            EventKind::FirstBrick { .. } => "FirstBrick",
+            EventKind::FirstBlood { .. } => "FirstBlood",
+            EventKind::TurretKilled { .. } => "TurretKilled",
+            EventKind::InhibKilled { .. } => "InhibKilled",
+            EventKind::InhibRespawningSoon { .. } => "InhibRespawningSoon",
+            EventKind::InhibRespawned { .. } => "InhibRespawned",
+            EventKind::DragonKill { .. } => "DragonKill",
+            EventKind::HeraldKill { .. } => "HeraldKill",
+            EventKind::HordeKill { .. } => "HordeKill",
+            EventKind::BaronKill { .. } => "BaronKill",
+            EventKind::AtakhanKill { .. } => "AtakhanKill",
+            EventKind::ChampionKill { .. } => "ChampionKill",
+            EventKind::Multikill { .. } => "Multikill",
+            EventKind::Ace { .. } => "Ace",
+            EventKind::GameEnd { .. } => "GameEnd",
+            EventKind::Unknown(raw) => raw
+                .get("EventName")
+                .and_then(|name| name.as_str())
+                .unwrap_or("Unknown"),
+        }
+    }
+}
+
+impl EventKind {
+    /// One line for the overlay, e.g. "Faker killed Deft"
+    pub fn summary(&self) -> String {
+        let stolen = |stolen: &bool| if *stolen { " (stolen)" } else { "" };
+        match self {
+            EventKind::GameStart => "Game started".to_string(),
+            EventKind::MinionsSpawning => "Minions spawning".to_string(),
+            EventKind::FirstBrick { killer } => format!("First tower to {}", killer),
+            EventKind::FirstBlood { recipient } => format!("First blood to {}", recipient),
+            EventKind::TurretKilled { killer, .. } => format!("{} took a turret", killer),
+            EventKind::InhibKilled { killer, .. } => format!("{} took an inhibitor", killer),
+            EventKind::InhibRespawningSoon { .. } => "Inhibitor respawning soon".to_string(),
+            EventKind::InhibRespawned { .. } => "Inhibitor respawned".to_string(),
+            EventKind::DragonKill {
+                dragon_type,
+                stolen: s,
+                killer,
+                ..
+            } => format!("{} took {:?} dragon{}", killer, dragon_type, stolen(s)),
+            EventKind::HeraldKill {
+                stolen: s, killer, ..
+            } => format!("{} took Herald{}", killer, stolen(s)),
+            EventKind::HordeKill {
+                stolen: s, killer, ..
+            } => format!("{} took a void grub{}", killer, stolen(s)),
+            EventKind::BaronKill {
+                stolen: s, killer, ..
+            } => format!("{} took Baron{}", killer, stolen(s)),
+            EventKind::AtakhanKill {
+                stolen: s, killer, ..
+            } => format!("{} took Atakhan{}", killer, stolen(s)),
+            EventKind::ChampionKill { killer, victim, .. } => {
+                format!("{} killed {}", killer, victim)
+            }
+            EventKind::Multikill {
+                killer,
+                kill_streak,
+            } => match kill_streak {
+                2 => format!("{} double kill", killer),
+                3 => format!("{} triple kill", killer),
+                4 => format!("{} quadra kill", killer),
+                5 => format!("{} PENTAKILL", killer),
+                n => format!("{} {} kills", killer, n),
+            },
+            EventKind::Ace { acing_team, .. } => format!("Ace by {}", acing_team),
+            EventKind::GameEnd { result } => format!("Game over: {}", result),
+            EventKind::Unknown(_) => self.name().to_string(),
+        }
+    }
+}
+
+//Riot sends Stolen as "True"/"False" strings
+mod bool_string {
+    use serde::de::{self, Deserializer};
+    use serde::{Deserialize, Serializer};
+
+    pub fn serialize<S>(value: &bool, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.serialize_str(if *value { "True" } else { "False" })
+    }
+
+    pub fn deserialize<'de, D>(deserializer: D) -> Result<bool, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        #[derive(Deserialize)]
+        #[serde(untagged)]
+        enum BoolOrString {
+            Bool(bool),
+            String(String),
+        }
+
+        match BoolOrString::deserialize(deserializer)? {
+            BoolOrString::Bool(b) => Ok(b),
+            BoolOrString::String(s) => match s.to_ascii_lowercase().as_str() {
+                "true" => Ok(true),
+                "false" => Ok(false),
+                other => Err(de::Error::custom(format!("invalid bool string: {}", other))),
+            },
+        }
+    }
+}
+
+/// The events block of /allgamedata, and all of /eventdata
+#[derive(Deserialize, Serialize, Debug, Default)]
+#[serde(default, rename_all = "PascalCase")]
 pub struct EventsWrapper {
+    /// Oldest first
     pub events: Vec<Event>,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn batch(events: serde_json::Value) -> Vec<Event> {
+        serde_json::from_value::<EventsWrapper>(json!({ "Events": events }))
+            .unwrap()
+            .events
+    }
+
+    #[test]
+    fn unknown_event_names_keep_the_batch_going() {
+        let raw = json!({
+            "EventID": 1,
+            "EventName": "FeatsOfStrength",
+            "EventTime": 600.5,
+            "Team": "ORDER",
+        });
+        let events = batch(json!([
+            { "EventID": 0, "EventName": "GameStart", "EventTime": 0.02 },
+            raw,
+            { "EventID": 2, "EventName": "GameEnd", "EventTime": 1500.0, "Result": "Win" },
+        ]));
+
+        assert_eq!(events.len(), 3);
+        assert_eq!(events[0].kind, EventKind::GameStart);
+        assert_eq!(events[1].event_id, 1);
+        assert_eq!(events[1].event_time, 600.5);
+        assert_eq!(events[1].kind, EventKind::Unknown(raw.clone()));
+        assert_eq!(events[1].kind.name(), "FeatsOfStrength");
+        // Written back out just as it came in
+        assert_eq!(serde_json::to_value(&events[1]).unwrap(), raw);
+        assert_eq!(
+            events[2].kind,
+            EventKind::GameEnd {
+                result: "Win".to_string()
+            }
+        );
+    }
+
+    #[test]
+    fn known_events_with_other_fields_still_parse() {
+        let events = batch(json!([
+            // A field Riot added, and no Assisters at all
+            {
+                "EventID": 0,
+                "EventName": "ChampionKill",
+                "EventTime": 300.0,
+                "KillerName": "Faker",
+                "VictimName": "Deft",
+                "KillerTeam": "ORDER",
+            },
+            // Missing one it needs, kept as it came instead of dropping the batch
+            { "EventID": 1, "EventName": "DragonKill", "EventTime": 330.0, "DragonType": "Fire" },
+            {
+                "EventID": 2,
+                "EventName": "DragonKill",
+                "EventTime": 900.0,
+                "DragonType": "Shadow",
+                "Stolen": "True",
+                "KillerName": "Faker",
+            },
+        ]));
+
+        assert_eq!(events.len(), 3);
+        assert_eq!(
+            events[0].kind,
+            EventKind::ChampionKill {
+                killer: "Faker".to_string(),
+                victim: "Deft".to_string(),
+                assisters: Vec::new(),
+            }
+        );
+        assert!(matches!(events[1].kind, EventKind::Unknown(_)));
+        assert_eq!(events[1].kind.name(), "DragonKill");
+        assert_eq!(events[1].event_time, 330.0);
+        assert_eq!(
+            events[2].kind,
+            EventKind::DragonKill {
+                dragon_type: DragonType::Other,
+                stolen: true,
+                killer: "Faker".to_string(),
+                assisters: Vec::new(),
+            }
+        );
+    }
+}
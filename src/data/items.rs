@@ -1,14 +1,27 @@
-use ::serde::Deserialize;
+use ::serde::{Deserialize, Serialize};
 
-#[derive(Deserialize, Debug)]
-#[serde(rename_all = "camelCase")]
+/// An item in someone's inventory
+#[derive(Deserialize, Serialize, Debug, Default)]
+#[serde(default, rename_all = "camelCase")]
 pub struct Item {
+    /// "Doran's Blade"
     #[serde(rename = "displayName")]
     pub name: String,
+    /// Has an active
     pub can_use: bool,
+    /// Used up when it's used, like potions
+    pub consumable: bool,
+    /// 0 to 5 for the inventory, 6 for the trinket
     pub slot: u8,
+    /// How many are stacked in the slot
     pub count: u8,
+    /// Gold for one
     pub price: u32,
+    /// The item's id in Data Dragon
     #[serde(rename = "itemID")]
     pub id: u32,
+    /// Localization key of the description
+    pub raw_description: String,
+    /// Localization key of the name
+    pub raw_display_name: String,
 }
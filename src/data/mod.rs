@@ -1,5 +1,10 @@
+/// /eventdata
 pub mod events;
+/// Inventories
 pub mod items;
+/// /activeplayer and /playerlist
 pub mod players;
+/// Rune pages
 pub mod runes;
+/// Summoner spells
 pub mod spells;
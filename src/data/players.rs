@@ -1,105 +1,199 @@
 use crate::data::items::Item;
-use crate::data::runes::Rune;
+use crate::data::runes::{FullRunes, Rune};
 use crate::data::spells::SummonerSpells;
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Deserialize)]
-#[serde(rename_all = "camelCase")]
+/// /activeplayerabilities, and later filled in from dragontail for everyone else
+#[derive(Debug, Deserialize, Serialize, Default)]
+#[serde(default, rename_all = "PascalCase")]
 pub struct Abilities {
+    /// The innate ability
     pub passive: AbilityInfo,
+    /// First basic ability
     pub q: AbilityInfo,
+    /// Second basic ability
     pub w: AbilityInfo,
+    /// Third basic ability
     pub e: AbilityInfo,
+    /// The ultimate
     pub r: AbilityInfo,
 }
 
-#[derive(Debug, Deserialize)]
-#[serde(rename_all = "camelCase")]
+/// One ability
+#[derive(Debug, Deserialize, Serialize, Default)]
+#[serde(default, rename_all = "camelCase")]
 pub struct AbilityInfo {
-    pub ability_level: u8,
+    /// The passive doesn't have one
+    pub ability_level: Option<u8>,
+    /// "Orb of Deception"
     pub display_name: String,
+    /// "AhriQ"
     pub id: String,
+    /// Localization key of the description
+    pub raw_description: String,
+    /// Localization key of the name
+    pub raw_display_name: String,
 }
 
-#[derive(Deserialize, Debug)]
-#[serde(rename_all = "camelCase")]
+/// Someone's line on the scoreboard
+#[derive(Deserialize, Serialize, Debug, Default)]
+#[serde(default, rename_all = "camelCase")]
 pub struct Score {
+    /// Assists
     pub assists: u16,
+    /// Deaths
     pub deaths: u16,
+    /// Kills
     pub kills: u16,
+    /// Minions and monsters killed, counted in 10s by the client
     pub creep_score: u16,
+    /// Vision score
     pub ward_score: f32,
 }
 
-#[derive(Clone, Debug, Deserialize)]
-#[serde(rename_all = "camelCase")]
+/// The active player's stats, as the client shows them on the stat panel
+#[derive(Clone, Debug, Deserialize, Serialize, Default)]
+#[serde(default, rename_all = "camelCase")]
 pub struct ChampionStats {
+    /// Ability haste
     pub ability_haste: f64,
+    /// Ability power
     pub ability_power: f64,
+    /// Armor
     pub armor: f64,
+    /// Flat armor penetration
     #[serde(rename = "armorPenetrationFlat")]
     pub lethality: f64,
+    /// Armor penetration, the multiplier on armor (1.0 for none)
     #[serde(rename = "armorPenetrationPercent")]
     pub armor_pen: f64,
+    /// Attack damage
     pub attack_damage: f64,
+    /// Attack range
     pub attack_range: f64,
+    /// Attacks per second
     pub attack_speed: f64,
+    /// Bonus armor penetration, the multiplier on bonus armor (1.0 for none)
     #[serde(rename = "bonusArmorPenetrationPercent")]
     pub bonus_armor_pen: f64,
+    /// Bonus magic penetration, the multiplier on bonus magic resist (1.0 for none)
     #[serde(rename = "bonusMagicPenetrationPercent")]
     pub bonus_magic_pen: f64,
+    /// Critical strike chance, 0 to 1
     pub crit_chance: f64,
+    /// Critical strike damage, 1.75 for 175%
     pub crit_damage: f64,
+    /// Health right now
     pub current_health: f64,
+    /// Heal and shield power
     pub heal_shield_power: f64,
+    /// Health regenerated per second
     pub health_regen_rate: f64,
+    /// Life steal
     pub life_steal: f64,
+    /// Flat magic penetration from lethality style sources
     pub magic_lethality: f64,
+    /// Flat magic penetration
     #[serde(rename = "magicPenetrationFlat")]
     pub magic_pen: f64,
+    /// Magic penetration, the multiplier on magic resist (1.0 for none)
     #[serde(rename = "magicPenetrationPercent")]
     pub magic_pen_percent: f64,
+    /// Magic resist
     pub magic_resist: f64,
+    /// Health when full
     pub max_health: f64,
+    /// Movement speed
     pub move_speed: f64,
+    /// Omnivamp
     pub omnivamp: f64,
+    /// Lethality
     pub physical_lethality: f64,
+    /// Physical vamp
     pub physical_vamp: f64,
+    /// Mana (or energy, fury, ...) when full
     pub resource_max: f64,
+    /// Mana (or energy, fury, ...) regenerated per second
     pub resource_regen_rate: f64,
+    /// "MANA", "ENERGY", "NONE", ...
     pub resource_type: String,
+    /// Mana (or energy, fury, ...) right now
     pub resource_value: f64,
+    /// Spell vamp
     pub spell_vamp: f64,
+    /// Tenacity
     pub tenacity: f64,
 }
 
-#[derive(Deserialize, Debug)]
-#[serde(rename_all = "camelCase")]
+/// /activeplayer, the player whose client this is
+#[derive(Deserialize, Serialize, Debug, Default)]
+#[serde(default, rename_all = "camelCase")]
 pub struct ActivePlayer {
+    /// "Name#TAG"
     pub riot_id: String,
+    /// The name half of the Riot ID
+    pub riot_id_game_name: String,
+    /// The tag half of the Riot ID
+    pub riot_id_tag_line: String,
+    /// Same as riot_id since Riot IDs replaced summoner names
+    pub summoner_name: String,
+    /// Stats as the stat panel shows them
     pub champion_stats: ChampionStats,
+    /// Champion level, 1 to 18
     pub level: u8,
+    /// Whether the client shows allies blue and enemies red
     pub team_relative_colors: bool,
+    /// Gold in the bank
     pub current_gold: f64,
+    /// Recordings from before these were read don't have them
+    pub abilities: Option<Abilities>,
+    /// The whole rune page
+    pub full_runes: Option<FullRunes>,
+    /// playerOp, which Riot doesn't document and usually leaves out
     pub player_op: Option<Player>,
 }
 
-#[derive(Deserialize, Debug)]
-#[serde(rename_all = "camelCase")]
+/// Someone in the game, from /playerlist
+#[derive(Deserialize, Serialize, Debug, Default)]
+#[serde(default, rename_all = "camelCase")]
 pub struct Player {
+    /// "Ahri"
     pub champion_name: String,
+    /// Localization key of the champion's name
+    pub raw_champion_name: String,
+    /// Filled by a bot
     pub is_bot: bool,
+    /// Waiting to respawn
     pub is_dead: bool,
+    /// Champion level, 1 to 18
     pub level: u8,
+    /// "TOP", "JUNGLE", "MIDDLE", "BOTTOM", "UTILITY", or empty outside of ranked and draft
     pub position: String,
+    /// Seconds left until they respawn
     pub respawn_timer: f32,
+    /// "Name#TAG"
     pub riot_id: String,
+    /// The name half of the Riot ID
+    pub riot_id_game_name: String,
+    /// The tag half of the Riot ID
+    pub riot_id_tag_line: String,
+    /// Same as riot_id since Riot IDs replaced summoner names
+    pub summoner_name: String,
+    /// Which skin, 0 for the base one
+    #[serde(rename = "skinID")]
+    pub skin_id: u32,
+    /// "ORDER" (blue) or "CHAOS" (red)
     pub team: String,
+    /// What's in their inventory
     pub items: Vec<Item>,
+    /// Keystone and trees, the whole page is only sent for the active player
     pub runes: Rune,
+    /// Kills, deaths, assists and the rest of the scoreboard
     pub scores: Score,
+    /// Summoner spells
     #[serde(rename = "summonerSpells")]
     pub spells: SummonerSpells,
+    /// Not in the payload, filled in from dragontail (see Dragontail::fill_abilities)
     #[serde(skip)]
     pub abilities: Option<Abilities>,
 }
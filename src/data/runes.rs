@@ -1,18 +1,55 @@
-use ::serde::Deserialize;
+use ::serde::{Deserialize, Serialize};
 
-#[derive(Deserialize, Debug)]
+/// A rune or rune tree
+#[derive(Deserialize, Serialize, Debug, Default)]
+#[serde(default, rename_all = "camelCase")]
 pub struct RuneType {
+    /// "Electrocute", "Domination"
     #[serde(rename = "displayName")]
     pub name: String,
+    /// The rune's id in Data Dragon
     pub id: u32,
+    /// Localization key of the description
     #[serde(rename = "rawDescription")]
     pub description: String,
+    /// Localization key of the name
+    pub raw_display_name: String,
 }
 
-#[derive(Deserialize, Debug)]
-#[serde(rename_all = "camelCase")]
+/// One of the three stat shards, they only come with an id and a description key
+#[derive(Deserialize, Serialize, Debug, Default)]
+#[serde(default, rename_all = "camelCase")]
+pub struct StatRune {
+    /// 5008 for adaptive force, and so on
+    pub id: u32,
+    /// Localization key of the description
+    pub raw_description: String,
+}
+
+/// /playermainrunes, and what /playerlist has for everyone
+#[derive(Deserialize, Serialize, Debug, Default)]
+#[serde(default, rename_all = "camelCase")]
 pub struct Rune {
+    /// The first rune of the primary tree
+    pub keystone: RuneType,
+    /// The tree the keystone is from
+    pub primary_rune_tree: RuneType,
+    /// The other tree
+    pub secondary_rune_tree: RuneType,
+}
+
+/// /activeplayerrunes, the whole page but only for the active player
+#[derive(Deserialize, Serialize, Debug, Default)]
+#[serde(default, rename_all = "camelCase")]
+pub struct FullRunes {
+    /// Every rune taken, keystone first
+    pub general_runes: Vec<RuneType>,
+    /// The first rune of the primary tree
     pub keystone: RuneType,
+    /// The tree the keystone is from
     pub primary_rune_tree: RuneType,
+    /// The other tree
     pub secondary_rune_tree: RuneType,
+    /// The three stat shards
+    pub stat_runes: Vec<StatRune>,
 }
@@ -1,16 +1,32 @@
-use ::serde::Deserialize;
+use ::serde::{Deserialize, Serialize};
 
-#[derive(Debug, Deserialize)]
-#[serde(rename_all = "camelCase")]
+/// The two summoner spells someone took
+#[derive(Debug, Deserialize, Serialize, Default)]
+#[serde(default, rename_all = "camelCase")]
 pub struct SummonerSpells {
+    /// On D by default
     pub summoner_spell_one: SummonerSpell,
+    /// On F by default
     pub summoner_spell_two: SummonerSpell,
 }
 
-#[derive(Debug, Deserialize)]
-#[serde(rename_all = "camelCase")]
+/// A summoner spell
+#[derive(Debug, Deserialize, Serialize, Default)]
+#[serde(default, rename_all = "camelCase")]
 pub struct SummonerSpell {
+    /// "Flash"
     pub display_name: String,
+    /// Localization key of the description
     pub raw_description: String,
+    /// Localization key of the name, the id is in it (see id)
     pub raw_display_name: String,
 }
+
+impl SummonerSpell {
+    /// "SummonerFlash" out of "GeneratedTip_SummonerSpell_SummonerFlash_DisplayName"
+    pub fn id(&self) -> Option<&str> {
+        self.raw_display_name
+            .strip_prefix("GeneratedTip_SummonerSpell_")?
+            .strip_suffix("_DisplayName")
+    }
+}
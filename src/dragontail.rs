@@ -0,0 +1,639 @@
+use crate::data::players::{Abilities, AbilityInfo, Player};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Path, PathBuf};
+
+/*
+Riot's Data Dragon, the static game data, read from an extracted dragontail bundle
+(https://ddragon.leagueoflegends.com/cdn/dragontail-<patch>.tgz) rather than fetched.
+A bundle has a folder per patch, each with the json for every locale and the images:
+    <bundle>/14.1.1/data/en_US/champion.json, championFull.json, item.json, runesReforged.json, summoner.json
+    <bundle>/14.1.1/img/champion/Ahri.png, img/item/1001.png, img/spell/SummonerFlash.png
+    <bundle>/img/perk-images/...   (rune icons aren't per patch)
+Everything is indexed the way the Live Client refers to it: champions by name, items, runes and
+summoner spells by id.
+*/
+
+/// Everything that can be wrong with a bundle, so the overlay can say what to fix
+#[derive(Debug, thiserror::Error)]
+pub enum DragontailError {
+    /// No patch folders in it
+    #[error(
+        "{path} isn't a dragontail bundle, point it at the folder with the patch folders (15.1.1, ...) in it"
+    )]
+    NotABundle {
+        /// The folder that was given
+        path: PathBuf,
+    },
+    /// The patch asked for isn't in the bundle
+    #[error("Patch {patch} isn't in {path}, it has {available}")]
+    MissingPatch {
+        /// The bundle
+        path: PathBuf,
+        /// The patch asked for
+        patch: String,
+        /// The patches it does have, comma separated
+        available: String,
+    },
+    /// The locale asked for isn't in the patch
+    #[error("Locale {locale} isn't in patch {patch}, it has {available}")]
+    MissingLocale {
+        /// The patch
+        patch: String,
+        /// The locale asked for
+        locale: String,
+        /// The locales it does have, comma separated
+        available: String,
+    },
+    /// A file says it's from a different patch than the folder it's in
+    #[error(
+        "{file} is from patch {found} but the bundle is {expected}, was another patch extracted over it?"
+    )]
+    PatchMismatch {
+        /// The file
+        file: PathBuf,
+        /// The version in the file
+        found: String,
+        /// The patch folder it's in
+        expected: String,
+    },
+    /// A file couldn't be read
+    #[error("Failed to read {file}: {source}")]
+    Read {
+        /// The file
+        file: PathBuf,
+        /// Why
+        source: std::io::Error,
+    },
+    /// A file isn't the JSON we expected
+    #[error("Failed to parse {file} at {path}: {message}")]
+    Parse {
+        /// The file
+        file: PathBuf,
+        /// Where in the JSON it went wrong
+        path: String,
+        /// What serde said
+        message: String,
+    },
+}
+
+/// The `[dragontail]` section of the config
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct DragontailConfig {
+    /// The extracted bundle, the folder with the patch folders in it. Not used if left out
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub path: Option<PathBuf>,
+    /// "en_US", "ko_KR", ...
+    pub locale: String,
+    /// Newest one in the bundle if left out
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub patch: Option<String>,
+}
+
+impl Default for DragontailConfig {
+    fn default() -> Self {
+        Self {
+            path: None,
+            locale: "en_US".to_string(),
+            patch: None,
+        }
+    }
+}
+
+/// Where an image is, relative to the patch's img folder
+#[derive(Debug, Clone, Deserialize)]
+pub struct Image {
+    /// The file name, "Ahri.png"
+    pub full: String,
+    /// The folder, "champion", "item", "spell", ...
+    pub group: String,
+}
+
+/// A champion from champion.json
+#[derive(Debug, Clone, Deserialize)]
+pub struct ChampionData {
+    /// "MonkeyKing", what the files are named after
+    pub id: String,
+    /// Its number, "62" for Wukong
+    pub key: String,
+    /// "Wukong", what the Live Client calls it
+    pub name: String,
+    /// "the Monkey King"
+    pub title: String,
+    /// "Fighter", "Tank", ...
+    pub tags: Vec<String>,
+    /// Square portrait
+    pub image: Image,
+    /// Only in championFull.json, filled in from there
+    #[serde(default)]
+    pub passive: Option<Passive>,
+    /// Q, W, E and R, only in championFull.json like the passive
+    #[serde(default)]
+    pub spells: Vec<ChampionSpell>,
+}
+
+/// A champion's passive
+#[derive(Debug, Clone, Deserialize)]
+pub struct Passive {
+    /// Its name
+    pub name: String,
+    /// Html-ish markup like the item descriptions
+    pub description: String,
+    /// Its icon
+    pub image: Image,
+}
+
+/// One of Q, W, E or R
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ChampionSpell {
+    /// "AhriQ"
+    pub id: String,
+    /// "Orb of Deception"
+    pub name: String,
+    /// Html-ish markup like the item descriptions
+    pub description: String,
+    /// How many ranks it has, 5 for basic abilities and 3 for ultimates
+    pub maxrank: u8,
+    /// Per rank, in seconds
+    pub cooldown: Vec<f64>,
+    /// Its icon
+    pub image: Image,
+}
+
+/// An item from item.json
+#[derive(Debug, Clone, Deserialize)]
+pub struct ItemData {
+    /// Filled in from the key it's under
+    #[serde(skip)]
+    pub id: u32,
+    /// "Doran's Blade"
+    pub name: String,
+    /// Html-ish markup, `<stats>`, `<passive>`, ...
+    pub description: String,
+    /// One line summary
+    pub plaintext: String,
+    /// What it costs
+    pub gold: Gold,
+    /// "Damage", "LifeSteal", ...
+    #[serde(default)]
+    pub tags: Vec<String>,
+    /// Item ids this builds from
+    #[serde(default)]
+    pub from: Vec<String>,
+    /// Item ids this builds into
+    #[serde(default)]
+    pub into: Vec<String>,
+    /// Its icon
+    pub image: Image,
+}
+
+/// An item's price
+#[derive(Debug, Clone, Deserialize)]
+pub struct Gold {
+    /// Without the items it builds from
+    pub base: u32,
+    /// With the items it builds from
+    pub total: u32,
+    /// What selling it gives back
+    pub sell: u32,
+    /// Whether it's in the shop
+    pub purchasable: bool,
+}
+
+/// A rune or a whole tree (tree is None then), the Live Client uses the same ids for both
+#[derive(Debug, Clone)]
+pub struct RuneData {
+    /// Its id, the same one the Live Client sends
+    pub id: u32,
+    /// "Electrocute"
+    pub key: String,
+    /// Its name in the bundle's locale
+    pub name: String,
+    /// Relative to the bundle's img folder
+    pub icon: String,
+    /// Html-ish markup
+    pub short_desc: String,
+    /// Html-ish markup, with the numbers
+    pub long_desc: String,
+    /// The id of the tree it's in, None for a tree
+    pub tree: Option<u32>,
+}
+
+/// A summoner spell from summoner.json
+#[derive(Debug, Clone, Deserialize)]
+pub struct SummonerSpellData {
+    /// "SummonerFlash"
+    pub id: String,
+    /// Its number, "4" for Flash
+    pub key: String,
+    /// "Flash"
+    pub name: String,
+    /// Html-ish markup like the item descriptions
+    pub description: String,
+    /// Per rank, in seconds (there's only one rank)
+    pub cooldown: Vec<f64>,
+    /// Its icon
+    pub image: Image,
+}
+
+// The layout of every file but runesReforged.json
+#[derive(Deserialize)]
+struct DataFile<T> {
+    version: String,
+    data: BTreeMap<String, T>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct RuneTreeFile {
+    id: u32,
+    key: String,
+    icon: String,
+    name: String,
+    slots: Vec<RuneSlotFile>,
+}
+
+#[derive(Deserialize)]
+struct RuneSlotFile {
+    runes: Vec<RuneFile>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct RuneFile {
+    id: u32,
+    key: String,
+    icon: String,
+    name: String,
+    short_desc: String,
+    long_desc: String,
+}
+
+/// One patch and locale of a bundle, loaded and indexed
+pub struct Dragontail {
+    bundle: PathBuf,
+    patch: String,
+    locale: String,
+    //By id, with champion_names to find them by the name the Live Client uses
+    champions: HashMap<String, ChampionData>,
+    champion_names: HashMap<String, String>,
+    items: HashMap<u32, ItemData>,
+    runes: HashMap<u32, RuneData>,
+    summoner_spells: HashMap<u32, SummonerSpellData>,
+}
+
+impl Dragontail {
+    /// Loads `patch` (the newest in the bundle if None) in `locale`
+    pub fn load(bundle: &Path, locale: &str, patch: Option<&str>) -> Result<Self, DragontailError> {
+        let patches = patches(bundle)?;
+        let patch = match patch {
+            Some(patch) if patches.iter().any(|p| p == patch) => patch.to_string(),
+            Some(patch) => {
+                return Err(DragontailError::MissingPatch {
+                    path: bundle.to_path_buf(),
+                    patch: patch.to_string(),
+                    available: patches.join(", "),
+                });
+            }
+            None => patches
+                .iter()
+                .max_by_key(|patch| version_key(patch))
+                .cloned()
+                .ok_or_else(|| DragontailError::NotABundle {
+                    path: bundle.to_path_buf(),
+                })?,
+        };
+
+        let data = bundle.join(&patch).join("data");
+        let dir = data.join(locale);
+        if !dir.is_dir() {
+            return Err(DragontailError::MissingLocale {
+                patch,
+                locale: locale.to_string(),
+                available: folders(&data).join(", "),
+            });
+        }
+
+        let mut champions = read_versioned::<ChampionData>(&dir.join("champion.json"), &patch)?;
+        let full = read_versioned::<ChampionData>(&dir.join("championFull.json"), &patch)?;
+        for (id, full) in full {
+            if let Some(champion) = champions.get_mut(&id) {
+                champion.passive = full.passive;
+                champion.spells = full.spells;
+            }
+        }
+        let champion_names = champions
+            .values()
+            .map(|champion| (champion.name.clone(), champion.id.clone()))
+            .collect();
+
+        let items = read_versioned::<ItemData>(&dir.join("item.json"), &patch)?
+            .into_iter()
+            .filter_map(|(id, mut item)| {
+                item.id = id.parse().ok()?;
+                Some((item.id, item))
+            })
+            .collect();
+
+        let mut runes = HashMap::new();
+        for tree in read::<Vec<RuneTreeFile>>(&dir.join("runesReforged.json"))? {
+            for rune in tree.slots.into_iter().flat_map(|slot| slot.runes) {
+                runes.insert(
+                    rune.id,
+                    RuneData {
+                        id: rune.id,
+                        key: rune.key,
+                        name: rune.name,
+                        icon: rune.icon,
+                        short_desc: rune.short_desc,
+                        long_desc: rune.long_desc,
+                        tree: Some(tree.id),
+                    },
+                );
+            }
+            runes.insert(
+                tree.id,
+                RuneData {
+                    id: tree.id,
+                    key: tree.key,
+                    name: tree.name,
+                    icon: tree.icon,
+                    short_desc: String::new(),
+                    long_desc: String::new(),
+                    tree: None,
+                },
+            );
+        }
+
+        let summoner_spells =
+            read_versioned::<SummonerSpellData>(&dir.join("summoner.json"), &patch)?
+                .into_values()
+                .filter_map(|spell| Some((spell.key.parse().ok()?, spell)))
+                .collect();
+
+        Ok(Dragontail {
+            bundle: bundle.to_path_buf(),
+            patch,
+            locale: locale.to_string(),
+            champions: champions.into_iter().collect(),
+            champion_names,
+            items,
+            runes,
+            summoner_spells,
+        })
+    }
+
+    /// "15.1.1"
+    pub fn patch(&self) -> &str {
+        &self.patch
+    }
+
+    /// "en_US"
+    pub fn locale(&self) -> &str {
+        &self.locale
+    }
+
+    /// By the name the Live Client uses ("Wukong") or the id ("MonkeyKing")
+    pub fn champion(&self, name: &str) -> Option<&ChampionData> {
+        let id = self.champion_names.get(name).map_or(name, String::as_str);
+        self.champions.get(id)
+    }
+
+    /// Every champion, in no particular order
+    pub fn champions(&self) -> impl Iterator<Item = &ChampionData> {
+        self.champions.values()
+    }
+
+    /// By the id the Live Client sends
+    pub fn item(&self, id: u32) -> Option<&ItemData> {
+        self.items.get(&id)
+    }
+
+    /// Every item, in no particular order
+    pub fn items(&self) -> impl Iterator<Item = &ItemData> {
+        self.items.values()
+    }
+
+    /// A rune or a tree, stat shards aren't in the bundle
+    pub fn rune(&self, id: u32) -> Option<&RuneData> {
+        self.runes.get(&id)
+    }
+
+    /// By its numeric key, 4 for Flash
+    pub fn summoner_spell(&self, id: u32) -> Option<&SummonerSpellData> {
+        self.summoner_spells.get(&id)
+    }
+
+    /// By the id in its raw name, e.g. "SummonerFlash" out of what the Live Client sends as
+    /// "GeneratedTip_SummonerSpell_SummonerFlash_DisplayName"
+    pub fn summoner_spell_named(&self, id: &str) -> Option<&SummonerSpellData> {
+        self.summoner_spells.values().find(|spell| spell.id == id)
+    }
+
+    /// A champion, item or summoner spell image
+    pub fn image_path(&self, image: &Image) -> PathBuf {
+        self.bundle
+            .join(&self.patch)
+            .join("img")
+            .join(&image.group)
+            .join(&image.full)
+    }
+
+    /// A rune's icon, runes live outside the patch folders
+    pub fn rune_icon_path(&self, rune: &RuneData) -> PathBuf {
+        self.bundle.join("img").join(&rune.icon)
+    }
+
+    /// A champion's passive and spells, without levels (the Live Client only has those for the active player)
+    pub fn abilities(&self, champion: &str) -> Option<Abilities> {
+        let champion = self.champion(champion)?;
+        let passive = champion.passive.as_ref()?;
+        let [q, w, e, r] = champion.spells.as_slice() else {
+            return None;
+        };
+        let spell = |spell: &ChampionSpell| AbilityInfo {
+            ability_level: None,
+            display_name: spell.name.clone(),
+            id: spell.id.clone(),
+            ..Default::default()
+        };
+        Some(Abilities {
+            passive: AbilityInfo {
+                ability_level: None,
+                display_name: passive.name.clone(),
+                id: format!("{}Passive", champion.id),
+                ..Default::default()
+            },
+            q: spell(q),
+            w: spell(w),
+            e: spell(e),
+            r: spell(r),
+        })
+    }
+
+    /// Fills in Player::abilities for everyone
+    pub fn fill_abilities(&self, players: &mut [Player]) {
+        for player in players {
+            player.abilities = self.abilities(&player.champion_name);
+        }
+    }
+}
+
+// Patch folders are the ones named like a version with a data folder in them
+fn patches(bundle: &Path) -> Result<Vec<String>, DragontailError> {
+    let patches: Vec<String> = folders(bundle)
+        .into_iter()
+        .filter(|name| {
+            name.split('.').all(|part| part.parse::<u32>().is_ok())
+                && bundle.join(name).join("data").is_dir()
+        })
+        .collect();
+    if patches.is_empty() {
+        return Err(DragontailError::NotABundle {
+            path: bundle.to_path_buf(),
+        });
+    }
+    Ok(patches)
+}
+
+fn folders(dir: &Path) -> Vec<String> {
+    let mut folders: Vec<String> = std::fs::read_dir(dir)
+        .into_iter()
+        .flatten()
+        .flatten()
+        .filter(|entry| entry.path().is_dir())
+        .filter_map(|entry| entry.file_name().into_string().ok())
+        .collect();
+    folders.sort();
+    folders
+}
+
+// "14.10.1" sorts after "14.9.1"
+fn version_key(patch: &str) -> Vec<u32> {
+    patch
+        .split('.')
+        .filter_map(|part| part.parse().ok())
+        .collect()
+}
+
+fn read<T: DeserializeOwned>(file: &Path) -> Result<T, DragontailError> {
+    let text = std::fs::read_to_string(file).map_err(|source| DragontailError::Read {
+        file: file.to_path_buf(),
+        source,
+    })?;
+    let deserializer = &mut serde_json::Deserializer::from_str(&text);
+    serde_path_to_error::deserialize(deserializer).map_err(|e| DragontailError::Parse {
+        file: file.to_path_buf(),
+        path: e.path().to_string(),
+        message: e.inner().to_string(),
+    })
+}
+
+// Reads one of the files that say which patch they're from, and checks it's the one asked for
+fn read_versioned<T: DeserializeOwned>(
+    file: &Path,
+    patch: &str,
+) -> Result<BTreeMap<String, T>, DragontailError> {
+    let contents: DataFile<T> = read(file)?;
+    if contents.version != patch {
+        return Err(DragontailError::PatchMismatch {
+            file: file.to_path_buf(),
+            found: contents.version,
+            expected: patch.to_string(),
+        });
+    }
+    Ok(contents.data)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fixtures() -> PathBuf {
+        Path::new(env!("CARGO_MANIFEST_DIR")).join("fixtures/dragontail")
+    }
+
+    #[test]
+    fn the_fixture_bundle_loads_and_indexes() {
+        let dragontail = Dragontail::load(&fixtures(), "en_US", None).unwrap();
+        assert_eq!(dragontail.patch(), "15.1.1");
+        assert_eq!(dragontail.champions().count(), 10);
+        assert_eq!(dragontail.items().count(), 20);
+
+        let lee_sin = dragontail.champion("Lee Sin").unwrap();
+        assert_eq!(lee_sin.id, "LeeSin");
+        assert!(dragontail.champion("LeeSin").is_some());
+        assert!(dragontail.image_path(&lee_sin.image).is_file());
+
+        let infinity_edge = dragontail.item(3031).unwrap();
+        assert_eq!(infinity_edge.name, "Infinity Edge");
+        assert!(dragontail.image_path(&infinity_edge.image).is_file());
+
+        assert_eq!(dragontail.summoner_spell(4).unwrap().id, "SummonerFlash");
+        assert_eq!(
+            dragontail.summoner_spell_named("SummonerDot").unwrap().key,
+            "14"
+        );
+
+        let electrocute = dragontail.rune(8112).unwrap();
+        assert_eq!(electrocute.tree, Some(8100));
+        assert!(dragontail.rune_icon_path(electrocute).is_file());
+        assert_eq!(dragontail.rune(8100).unwrap().tree, None);
+    }
+
+    #[test]
+    fn abilities_come_from_the_full_champion_file() {
+        let dragontail = Dragontail::load(&fixtures(), "en_US", Some("15.1.1")).unwrap();
+        let mut players = vec![
+            Player {
+                champion_name: "Ahri".to_string(),
+                ..Default::default()
+            },
+            Player {
+                champion_name: "Nobody".to_string(),
+                ..Default::default()
+            },
+        ];
+        dragontail.fill_abilities(&mut players);
+        let abilities = players[0].abilities.as_ref().unwrap();
+        assert_eq!(abilities.passive.id, "AhriPassive");
+        assert_eq!(abilities.r.id, "AhriR");
+        assert_eq!(abilities.q.ability_level, None);
+        assert!(players[1].abilities.is_none());
+    }
+
+    #[test]
+    fn missing_patches_and_locales_say_what_there_is() {
+        let error = Dragontail::load(&fixtures(), "en_US", Some("14.1.1"))
+            .err()
+            .unwrap();
+        assert!(
+            matches!(error, DragontailError::MissingPatch { available, .. } if available == "15.1.1")
+        );
+        let error = Dragontail::load(&fixtures(), "xx_XX", None).err().unwrap();
+        assert!(
+            matches!(error, DragontailError::MissingLocale { available, .. } if available == "en_US")
+        );
+        let error = Dragontail::load(&fixtures().join("15.1.1"), "en_US", None)
+            .err()
+            .unwrap();
+        assert!(matches!(error, DragontailError::NotABundle { .. }));
+    }
+
+    #[test]
+    fn the_newest_patch_is_picked_by_version_not_by_name() {
+        let bundle = tempfile::tempdir().unwrap();
+        for patch in ["15.2.1", "15.10.1", "14.24.1"] {
+            std::fs::create_dir_all(bundle.path().join(patch).join("data")).unwrap();
+        }
+        let error = Dragontail::load(bundle.path(), "en_US", None)
+            .err()
+            .unwrap();
+        assert!(
+            matches!(error, DragontailError::MissingLocale { patch, .. } if patch == "15.10.1")
+        );
+    }
+}
@@ -0,0 +1,170 @@
+use crate::data::events::Event;
+
+/*
+Keeps track of the last EventID we handed to the UI.
+The live fetcher only asks for the end of the log, /eventdata?eventID=<next_event_id()>, which starts
+at the newest event we've already seen so there's something to check it against (advance_page).
+Recordings and stdin still come with the whole log every time (advance).
+The log starts over on a new game (or reconnecting to a different one), so the cursor has to notice
+when the log no longer lines up with what we've already seen and start over too.
+*/
+
+/// What came out of the cursor
+pub enum CursorUpdate {
+    /// Events that arrived since the last call, oldest first (can be empty)
+    New(Vec<Event>),
+    /// The log was replaced, these are all of the events in the new log
+    Reset(Vec<Event>),
+}
+
+/// The newest event handed out so far (see the top of the file)
+#[derive(Default)]
+pub struct EventCursor {
+    //Id and time of the newest event we've seen, time is used to tell two logs apart
+    last_seen: Option<(u32, f64)>,
+}
+
+impl EventCursor {
+    /// Nothing seen yet
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Takes the whole log, returns what's new in it or Reset if it's a different log
+    pub fn advance(&mut self, events: Vec<Event>) -> CursorUpdate {
+        let previous = self.last_seen;
+        self.last_seen = events
+            .iter()
+            .max_by_key(|e| e.event_id)
+            .map(|e| (e.event_id, e.event_time));
+
+        let Some((last_id, last_time)) = previous else {
+            return CursorUpdate::New(events);
+        };
+
+        // Same event id but a different time means it's a different game's log
+        let still_same_log = events
+            .iter()
+            .any(|e| e.event_id == last_id && e.event_time == last_time);
+        if !still_same_log {
+            return CursorUpdate::Reset(events);
+        }
+
+        CursorUpdate::New(
+            events
+                .into_iter()
+                .filter(|e| e.event_id > last_id)
+                .collect(),
+        )
+    }
+
+    /// What to ask /eventdata for: the newest event we've seen (it comes back too, showing the log
+    /// is still the same one), or 0 for the whole log
+    pub fn next_event_id(&self) -> u32 {
+        self.last_seen.map_or(0, |(id, _)| id)
+    }
+
+    /// Takes /eventdata?eventID=next_event_id() and hands back the events that are new, None if
+    /// they don't carry on from what we've seen (a different game), then it's reset() and the
+    /// whole log has to be asked for
+    pub fn advance_page(&mut self, events: Vec<Event>) -> Option<Vec<Event>> {
+        if let Some((last_id, last_time)) = self.last_seen
+            && !events
+                .iter()
+                .any(|e| e.event_id == last_id && e.event_time == last_time)
+        {
+            return None;
+        }
+        match self.advance(events) {
+            CursorUpdate::New(events) | CursorUpdate::Reset(events) => Some(events),
+        }
+    }
+
+    /// Forget everything, returns true if there was anything to forget
+    pub fn reset(&mut self) -> bool {
+        self.last_seen.take().is_some()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data::events::EventKind;
+
+    fn log(events: &[(u32, f64)]) -> Vec<Event> {
+        events
+            .iter()
+            .map(|&(event_id, event_time)| Event {
+                event_id,
+                event_time,
+                kind: EventKind::MinionsSpawning,
+            })
+            .collect()
+    }
+
+    fn ids(update: CursorUpdate) -> (bool, Vec<u32>) {
+        match update {
+            CursorUpdate::New(events) => (false, events.iter().map(|e| e.event_id).collect()),
+            CursorUpdate::Reset(events) => (true, events.iter().map(|e| e.event_id).collect()),
+        }
+    }
+
+    #[test]
+    fn only_new_events_come_through() {
+        let mut cursor = EventCursor::new();
+        assert_eq!(
+            ids(cursor.advance(log(&[(0, 0.1), (1, 65.0)]))),
+            (false, vec![0, 1])
+        );
+        assert_eq!(
+            ids(cursor.advance(log(&[(0, 0.1), (1, 65.0)]))),
+            (false, vec![])
+        );
+        assert_eq!(
+            ids(cursor.advance(log(&[(0, 0.1), (1, 65.0), (2, 90.0)]))),
+            (false, vec![2])
+        );
+    }
+
+    #[test]
+    fn a_different_log_resets() {
+        let mut cursor = EventCursor::new();
+        cursor.advance(log(&[(0, 0.1), (1, 65.0), (2, 90.0)]));
+        // Same ids, different times: a new game
+        assert_eq!(
+            ids(cursor.advance(log(&[(0, 0.2), (1, 30.0)]))),
+            (true, vec![0, 1])
+        );
+        // Shorter than what we had
+        assert_eq!(ids(cursor.advance(log(&[(0, 0.3)]))), (true, vec![0]));
+    }
+
+    #[test]
+    fn pages_start_at_the_newest_event_seen() {
+        let mut cursor = EventCursor::new();
+        assert_eq!(cursor.next_event_id(), 0);
+        let events = cursor.advance_page(log(&[(0, 0.1), (1, 65.0)])).unwrap();
+        assert_eq!(events.len(), 2);
+        assert_eq!(cursor.next_event_id(), 1);
+
+        let events = cursor.advance_page(log(&[(1, 65.0), (2, 90.0)])).unwrap();
+        assert_eq!(events.iter().map(|e| e.event_id).collect::<Vec<_>>(), [2]);
+        assert!(cursor.advance_page(log(&[(2, 90.0)])).unwrap().is_empty());
+        assert_eq!(cursor.next_event_id(), 2);
+    }
+
+    #[test]
+    fn a_page_from_another_game_is_turned_down() {
+        let mut cursor = EventCursor::new();
+        cursor.advance_page(log(&[(0, 0.1), (1, 65.0), (2, 90.0)]));
+        // The new game's log is shorter, so the page comes back empty
+        assert!(cursor.advance_page(Vec::new()).is_none());
+        // Or it has the id but not the time
+        assert!(cursor.advance_page(log(&[(2, 40.0)])).is_none());
+        // Nothing was forgotten until reset() is called
+        assert_eq!(cursor.next_event_id(), 2);
+        assert!(cursor.reset());
+        assert!(!cursor.reset());
+        assert_eq!(cursor.next_event_id(), 0);
+    }
+}
@@ -0,0 +1,558 @@
+use anyhow::{Result, bail};
+use std::collections::HashMap;
+
+/*
+The little language stat rows are written in (see stat_rows.rs for the variables it can see).
+Numbers only, true and false are 1 and 0:
+    100 * (1 - armor_pen)
+    lethality > 0 || armor_pen < 1
+    deaths == 0 ? kills + assists : (kills + assists) / deaths
+Operators, loosest first: ?:, ||, &&, comparisons (< <= > >= == !=), + -, * / %, unary - and !
+Functions: min, max, abs, round, floor, ceil, clamp(x, lo, hi)
+A Template is text with {expression} or {expression:spec} in it, spec is .N for N decimals
+or "time" for m:ss. Everything is checked when the config loads, so evaluating can't fail:
+a missing variable or a division by zero just shows "-".
+*/
+
+//Deep enough for anything sane, shallow enough not to blow the stack on garbage
+const MAX_DEPTH: usize = 64;
+
+pub type Vars = HashMap<String, f64>;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Expr {
+    Number(f64),
+    Var(String),
+    Unary(UnaryOp, Box<Expr>),
+    Binary(BinaryOp, Box<Expr>, Box<Expr>),
+    Ternary(Box<Expr>, Box<Expr>, Box<Expr>),
+    Call(Function, Vec<Expr>),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum UnaryOp {
+    Neg,
+    Not,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum BinaryOp {
+    Add,
+    Sub,
+    Mul,
+    Div,
+    Rem,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+    Eq,
+    Ne,
+    And,
+    Or,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Function {
+    Min,
+    Max,
+    Abs,
+    Round,
+    Floor,
+    Ceil,
+    Clamp,
+}
+
+impl Function {
+    fn from_name(name: &str) -> Option<(Function, usize)> {
+        let function = match name {
+            "min" => (Function::Min, 2),
+            "max" => (Function::Max, 2),
+            "abs" => (Function::Abs, 1),
+            "round" => (Function::Round, 1),
+            "floor" => (Function::Floor, 1),
+            "ceil" => (Function::Ceil, 1),
+            "clamp" => (Function::Clamp, 3),
+            _ => return None,
+        };
+        Some(function)
+    }
+}
+
+impl Expr {
+    pub fn parse(text: &str) -> Result<Expr> {
+        let tokens = tokenize(text)?;
+        let mut parser = Parser {
+            tokens,
+            position: 0,
+            depth: 0,
+        };
+        let expr = parser.expression()?;
+        if let Some(token) = parser.peek() {
+            bail!("Unexpected {} in {:?}", token.describe(), text);
+        }
+        Ok(expr)
+    }
+
+    pub fn eval(&self, vars: &Vars) -> f64 {
+        match self {
+            Expr::Number(n) => *n,
+            Expr::Var(name) => vars.get(name).copied().unwrap_or(f64::NAN),
+            Expr::Unary(UnaryOp::Neg, e) => -e.eval(vars),
+            Expr::Unary(UnaryOp::Not, e) => truth(e.eval(vars) == 0.0),
+            Expr::Binary(op, a, b) => {
+                // Short circuit so `deaths > 0 && kills / deaths > 3` behaves
+                match op {
+                    BinaryOp::And => return truth(a.is_true(vars) && b.is_true(vars)),
+                    BinaryOp::Or => return truth(a.is_true(vars) || b.is_true(vars)),
+                    _ => {}
+                }
+                let (a, b) = (a.eval(vars), b.eval(vars));
+                match op {
+                    BinaryOp::Add => a + b,
+                    BinaryOp::Sub => a - b,
+                    BinaryOp::Mul => a * b,
+                    BinaryOp::Div => a / b,
+                    BinaryOp::Rem => a % b,
+                    BinaryOp::Lt => truth(a < b),
+                    BinaryOp::Le => truth(a <= b),
+                    BinaryOp::Gt => truth(a > b),
+                    BinaryOp::Ge => truth(a >= b),
+                    BinaryOp::Eq => truth(a == b),
+                    BinaryOp::Ne => truth(a != b),
+                    BinaryOp::And | BinaryOp::Or => unreachable!("handled above"),
+                }
+            }
+            Expr::Ternary(condition, then, otherwise) => {
+                if condition.is_true(vars) {
+                    then.eval(vars)
+                } else {
+                    otherwise.eval(vars)
+                }
+            }
+            Expr::Call(function, args) => {
+                let arg = |i: usize| args[i].eval(vars);
+                match function {
+                    Function::Min => arg(0).min(arg(1)),
+                    Function::Max => arg(0).max(arg(1)),
+                    Function::Abs => arg(0).abs(),
+                    Function::Round => arg(0).round(),
+                    Function::Floor => arg(0).floor(),
+                    Function::Ceil => arg(0).ceil(),
+                    Function::Clamp => arg(0).max(arg(1)).min(arg(2)),
+                }
+            }
+        }
+    }
+
+    /// Anything but 0 (or NaN) counts as true
+    pub fn is_true(&self, vars: &Vars) -> bool {
+        let value = self.eval(vars);
+        value != 0.0 && !value.is_nan()
+    }
+
+    /// Every variable the expression reads, so the config can be checked up front
+    pub fn variables(&self) -> Vec<&str> {
+        let mut names = Vec::new();
+        self.collect_variables(&mut names);
+        names
+    }
+
+    fn collect_variables<'a>(&'a self, names: &mut Vec<&'a str>) {
+        match self {
+            Expr::Number(_) => {}
+            Expr::Var(name) => names.push(name),
+            Expr::Unary(_, e) => e.collect_variables(names),
+            Expr::Binary(_, a, b) => {
+                a.collect_variables(names);
+                b.collect_variables(names);
+            }
+            Expr::Ternary(condition, then, otherwise) => {
+                condition.collect_variables(names);
+                then.collect_variables(names);
+                otherwise.collect_variables(names);
+            }
+            Expr::Call(_, args) => args.iter().for_each(|arg| arg.collect_variables(names)),
+        }
+    }
+}
+
+fn truth(value: bool) -> f64 {
+    if value { 1.0 } else { 0.0 }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Number(f64),
+    Ident(String),
+    Op(&'static str),
+}
+
+impl Token {
+    fn describe(&self) -> String {
+        match self {
+            Token::Number(n) => format!("number {}", n),
+            Token::Ident(name) => format!("{:?}", name),
+            Token::Op(op) => format!("'{}'", op),
+        }
+    }
+}
+
+// Longest first so "<=" isn't read as "<" then "="
+const OPERATORS: [&str; 19] = [
+    "<=", ">=", "==", "!=", "&&", "||", "+", "-", "*", "/", "%", "<", ">", "!", "(", ")", ",", "?",
+    ":",
+];
+
+fn tokenize(text: &str) -> Result<Vec<Token>> {
+    let mut tokens = Vec::new();
+    let mut rest = text;
+    while let Some(c) = rest.chars().next() {
+        if c.is_whitespace() {
+            rest = &rest[c.len_utf8()..];
+        } else if c.is_ascii_digit() || c == '.' {
+            let end = rest
+                .find(|c: char| !(c.is_ascii_digit() || c == '.'))
+                .unwrap_or(rest.len());
+            let number = rest[..end]
+                .parse()
+                .map_err(|_| anyhow::anyhow!("{:?} isn't a number", &rest[..end]))?;
+            tokens.push(Token::Number(number));
+            rest = &rest[end..];
+        } else if c.is_ascii_alphabetic() || c == '_' {
+            let end = rest
+                .find(|c: char| !(c.is_ascii_alphanumeric() || c == '_'))
+                .unwrap_or(rest.len());
+            let word = &rest[..end];
+            tokens.push(match word {
+                "true" => Token::Number(1.0),
+                "false" => Token::Number(0.0),
+                _ => Token::Ident(word.to_string()),
+            });
+            rest = &rest[end..];
+        } else if let Some(op) = OPERATORS.iter().find(|op| rest.starts_with(*op)) {
+            tokens.push(Token::Op(op));
+            rest = &rest[op.len()..];
+        } else {
+            bail!("Unexpected {:?} in {:?}", c, text);
+        }
+    }
+    Ok(tokens)
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    position: usize,
+    depth: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.position)
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.position).cloned();
+        self.position += 1;
+        token
+    }
+
+    fn eat(&mut self, op: &str) -> bool {
+        if matches!(self.peek(), Some(Token::Op(found)) if *found == op) {
+            self.position += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expect(&mut self, op: &str) -> Result<()> {
+        if !self.eat(op) {
+            match self.peek() {
+                Some(token) => bail!("Expected '{}', found {}", op, token.describe()),
+                None => bail!("Expected '{}' at the end", op),
+            }
+        }
+        Ok(())
+    }
+
+    fn expression(&mut self) -> Result<Expr> {
+        self.depth += 1;
+        if self.depth > MAX_DEPTH {
+            bail!("Expression is nested too deeply");
+        }
+        let condition = self.binary(0)?;
+        let expr = if self.eat("?") {
+            let then = self.expression()?;
+            self.expect(":")?;
+            let otherwise = self.expression()?;
+            Expr::Ternary(Box::new(condition), Box::new(then), Box::new(otherwise))
+        } else {
+            condition
+        };
+        self.depth -= 1;
+        Ok(expr)
+    }
+
+    // Precedence climbing over BINARY_LEVELS, loosest level first
+    fn binary(&mut self, level: usize) -> Result<Expr> {
+        let Some(ops) = BINARY_LEVELS.get(level) else {
+            return self.unary();
+        };
+        let mut left = self.binary(level + 1)?;
+        while let Some(Token::Op(found)) = self.peek()
+            && let Some((_, op)) = ops.iter().find(|(text, _)| text == found)
+        {
+            let op = *op;
+            self.position += 1;
+            let right = self.binary(level + 1)?;
+            left = Expr::Binary(op, Box::new(left), Box::new(right));
+        }
+        Ok(left)
+    }
+
+    fn unary(&mut self) -> Result<Expr> {
+        if self.eat("-") {
+            return Ok(Expr::Unary(UnaryOp::Neg, Box::new(self.nested_unary()?)));
+        }
+        if self.eat("!") {
+            return Ok(Expr::Unary(UnaryOp::Not, Box::new(self.nested_unary()?)));
+        }
+        self.primary()
+    }
+
+    fn nested_unary(&mut self) -> Result<Expr> {
+        self.depth += 1;
+        if self.depth > MAX_DEPTH {
+            bail!("Expression is nested too deeply");
+        }
+        let expr = self.unary();
+        self.depth -= 1;
+        expr
+    }
+
+    fn primary(&mut self) -> Result<Expr> {
+        match self.next() {
+            Some(Token::Number(n)) => Ok(Expr::Number(n)),
+            Some(Token::Ident(name)) => {
+                if !self.eat("(") {
+                    return Ok(Expr::Var(name));
+                }
+                let Some((function, arity)) = Function::from_name(&name) else {
+                    bail!("Unknown function {:?}", name);
+                };
+                let mut args = Vec::new();
+                if !self.eat(")") {
+                    loop {
+                        args.push(self.expression()?);
+                        if self.eat(")") {
+                            break;
+                        }
+                        self.expect(",")?;
+                    }
+                }
+                if args.len() != arity {
+                    bail!("{} takes {} arguments, got {}", name, arity, args.len());
+                }
+                Ok(Expr::Call(function, args))
+            }
+            Some(Token::Op("(")) => {
+                let expr = self.expression()?;
+                self.expect(")")?;
+                Ok(expr)
+            }
+            Some(token) => bail!("Unexpected {}", token.describe()),
+            None => bail!("Expression ends too early"),
+        }
+    }
+}
+
+const BINARY_LEVELS: [&[(&str, BinaryOp)]; 5] = [
+    &[("||", BinaryOp::Or)],
+    &[("&&", BinaryOp::And)],
+    &[
+        ("<=", BinaryOp::Le),
+        (">=", BinaryOp::Ge),
+        ("==", BinaryOp::Eq),
+        ("!=", BinaryOp::Ne),
+        ("<", BinaryOp::Lt),
+        (">", BinaryOp::Gt),
+    ],
+    &[("+", BinaryOp::Add), ("-", BinaryOp::Sub)],
+    &[
+        ("*", BinaryOp::Mul),
+        ("/", BinaryOp::Div),
+        ("%", BinaryOp::Rem),
+    ],
+];
+
+/// Text with {expression:spec} holes, e.g. "{lethality:.0} | %{armor_pen_pct:.2}"
+#[derive(Debug, Clone, PartialEq)]
+pub struct Template {
+    parts: Vec<Part>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Part {
+    Text(String),
+    Value(Expr, Spec),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Spec {
+    //Whole numbers as they are, anything else with 2 decimals
+    Auto,
+    Decimals(usize),
+    //Seconds as m:ss
+    Time,
+}
+
+impl Spec {
+    fn parse(text: &str) -> Option<Spec> {
+        match text.trim() {
+            "time" => Some(Spec::Time),
+            spec => spec
+                .strip_prefix('.')
+                .and_then(|decimals| decimals.parse().ok())
+                .filter(|decimals| *decimals <= 10)
+                .map(Spec::Decimals),
+        }
+    }
+
+    fn format(&self, value: f64) -> String {
+        if !value.is_finite() {
+            return "-".to_string();
+        }
+        match self {
+            Spec::Auto if value.fract() == 0.0 => format!("{:.0}", value),
+            Spec::Auto => format!("{:.2}", value),
+            Spec::Decimals(decimals) => format!("{:.*}", decimals, value),
+            Spec::Time => {
+                let seconds = value.max(0.0).round() as u64;
+                format!("{}:{:02}", seconds / 60, seconds % 60)
+            }
+        }
+    }
+}
+
+impl Template {
+    pub fn parse(text: &str) -> Result<Template> {
+        let mut parts = Vec::new();
+        let mut literal = String::new();
+        let mut chars = text.char_indices().peekable();
+        while let Some((i, c)) = chars.next() {
+            match c {
+                '{' if chars.peek().is_some_and(|(_, next)| *next == '{') => {
+                    chars.next();
+                    literal.push('{');
+                }
+                '}' if chars.peek().is_some_and(|(_, next)| *next == '}') => {
+                    chars.next();
+                    literal.push('}');
+                }
+                '{' => {
+                    let Some(length) = text[i + 1..].find('}') else {
+                        bail!("Missing '}}' in {:?}", text);
+                    };
+                    let inside = &text[i + 1..i + 1 + length];
+                    while chars.next_if(|(j, _)| *j <= i + 1 + length).is_some() {}
+                    if !literal.is_empty() {
+                        parts.push(Part::Text(std::mem::take(&mut literal)));
+                    }
+                    parts.push(placeholder(inside)?);
+                }
+                '}' => bail!("Unmatched '}}' in {:?}, write '}}}}' for a brace", text),
+                c => literal.push(c),
+            }
+        }
+        if !literal.is_empty() {
+            parts.push(Part::Text(literal));
+        }
+        Ok(Template { parts })
+    }
+
+    pub fn render(&self, vars: &Vars) -> String {
+        self.parts
+            .iter()
+            .map(|part| match part {
+                Part::Text(text) => text.clone(),
+                Part::Value(expr, spec) => spec.format(expr.eval(vars)),
+            })
+            .collect()
+    }
+
+    pub fn variables(&self) -> Vec<&str> {
+        self.parts
+            .iter()
+            .flat_map(|part| match part {
+                Part::Text(_) => Vec::new(),
+                Part::Value(expr, _) => expr.variables(),
+            })
+            .collect()
+    }
+}
+
+// The spec is whatever follows the last ':' if it looks like one, so ?: still works inside
+fn placeholder(inside: &str) -> Result<Part> {
+    if let Some((expr, spec)) = inside.rsplit_once(':')
+        && let Some(spec) = Spec::parse(spec)
+    {
+        return Ok(Part::Value(Expr::parse(expr)?, spec));
+    }
+    Ok(Part::Value(Expr::parse(inside)?, Spec::Auto))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn eval(text: &str) -> f64 {
+        let vars = Vars::from([("kills".to_string(), 4.0), ("deaths".to_string(), 0.0)]);
+        Expr::parse(text).unwrap().eval(&vars)
+    }
+
+    #[test]
+    fn operators_bind_in_the_documented_order() {
+        assert_eq!(eval("1 + 2 * 3"), 7.0);
+        assert_eq!(eval("(1 + 2) * 3"), 9.0);
+        assert_eq!(eval("10 - 4 - 3"), 3.0);
+        assert_eq!(eval("1 + 1 > 1 && 0 || 1"), 1.0);
+        assert_eq!(eval("kills > 3 ? 1 + 1 : 5"), 2.0);
+        assert_eq!(eval("deaths == 0 ? kills : kills / deaths"), 4.0);
+    }
+
+    #[test]
+    fn unary_minus_binds_tighter_than_anything_binary() {
+        assert_eq!(eval("-2 * 3"), -6.0);
+        assert_eq!(eval("2 - -1"), 3.0);
+        assert_eq!(eval("--kills"), 4.0);
+        assert_eq!(eval("!deaths + 1"), 2.0);
+    }
+
+    #[test]
+    fn dividing_by_zero_or_a_missing_variable_shows_a_dash() {
+        let vars = Vars::from([("deaths".to_string(), 0.0)]);
+        let template = Template::parse("{1 / deaths} {0 / deaths:.1} {gold:time}").unwrap();
+        assert_eq!(template.render(&vars), "- - -");
+        assert_eq!(template.variables(), ["deaths", "deaths", "gold"]);
+    }
+
+    #[test]
+    fn mistakes_are_caught_when_parsing() {
+        assert!(Expr::parse("1 +").is_err());
+        assert!(Expr::parse("max(1)").is_err());
+        assert!(Expr::parse("nope(1)").is_err());
+        assert!(Template::parse("{kills").is_err());
+    }
+
+    #[test]
+    fn nesting_past_the_limit_is_an_error() {
+        let nested = |depth: usize| format!("{}1{}", "(".repeat(depth), ")".repeat(depth));
+        assert_eq!(
+            Expr::parse(&nested(MAX_DEPTH / 2)).unwrap(),
+            Expr::Number(1.0)
+        );
+        assert!(Expr::parse(&nested(MAX_DEPTH + 1)).is_err());
+        assert!(Expr::parse(&"-".repeat(10_000)).is_err());
+    }
+}
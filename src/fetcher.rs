@@ -1,37 +1,623 @@
-use crate::{GameInfo, live_client::LoLLiveClient};
+use crate::GameInfo;
+use crate::connection::{ConnectionState, ConnectionStatus, ConnectionTracker};
+use crate::data::events::{Event, EventKind};
+use crate::event_cursor::{CursorUpdate, EventCursor};
+use crate::game_info::GameUpdate;
+use crate::live_client::{LiveClientConfig, LiveClientError, LoLLiveClient};
+use crate::polling::{Backoff, Endpoint, PollConfig, Schedule};
+use crate::recorder::Recorder;
 use anyhow::Result;
-use tokio::sync::mpsc;
+use serde_json::{Value, json};
+use std::collections::HashMap;
+use tokio::sync::{mpsc, watch};
+use tokio::task::JoinHandle;
 
-pub async fn game_data_fetcher(sender: mpsc::UnboundedSender<GameInfo>) -> Result<()> {
-    println!("Starting League of Legends Live Client API connection...");
-    let client = LoLLiveClient::new()?;
+/*
+Follows the live game: waits for League to start one, polls its endpoints (see polling.rs) while it
+lasts and sends the overlay a GameUpdate for every change. Other tools get the same updates from
+GameUpdates without having to set up the channels themselves.
+*/
+
+/// What the fetcher needs to know, a new one can be sent while it runs
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct FetcherConfig {
+    /// Where the Live Client is and which certificate to trust
+    pub live_client: LiveClientConfig,
+    /// How often each endpoint is polled
+    pub poll: PollConfig,
+}
+
+/// Typed updates from the live game, polled in the background for as long as this is kept around
+pub struct GameUpdates {
+    receiver: mpsc::UnboundedReceiver<GameUpdate>,
+    config: watch::Sender<FetcherConfig>,
+    task: JoinHandle<Result<()>>,
+}
+
+impl GameUpdates {
+    /// Starts polling, has to be called from inside a tokio runtime
+    pub fn spawn(config: FetcherConfig) -> Result<Self> {
+        let client = LoLLiveClient::new(&config.live_client)?;
+        let (sender, receiver) = mpsc::unbounded_channel();
+        let (config, config_receiver) = watch::channel(config);
+        let task = tokio::spawn(game_data_fetcher(sender, client, None, config_receiver));
+        Ok(GameUpdates {
+            receiver,
+            config,
+            task,
+        })
+    }
+
+    /// The next update, None once the fetcher has stopped
+    pub async fn next(&mut self) -> Option<GameUpdate> {
+        self.receiver.recv().await
+    }
+
+    /// Switches to new settings, reconnecting if the Live Client ones changed
+    pub fn reconfigure(&self, config: FetcherConfig) {
+        self.config.send_if_modified(|current| {
+            let changed = *current != config;
+            *current = config;
+            changed
+        });
+    }
+}
+
+impl Drop for GameUpdates {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
+
+/// Polls until `sender` goes away, the loop behind GameUpdates and the overlay's live source
+pub async fn game_data_fetcher(
+    sender: mpsc::UnboundedSender<GameUpdate>,
+    mut client: LoLLiveClient,
+    mut recorder: Option<Recorder>,
+    mut config: watch::Receiver<FetcherConfig>,
+) -> Result<()> {
+    log::info!("Starting League of Legends Live Client API connection...");
+    let mut cursor = EventCursor::new();
+    let mut connection = ConnectionTracker::new();
+    let FetcherConfig {
+        mut live_client,
+        mut poll,
+    } = config.borrow_and_update().clone();
+    let mut backoff = Backoff::new(&poll);
 
     loop {
-        if !client.is_game_active().await {
-            println!("No active game detected. Waiting...");
-            tokio::time::sleep(tokio::time::Duration::from_secs(5)).await;
-            continue;
-        }
-
-        match client.get_all_game_data().await {
-            Ok(data) => {
-                // Try to parse the game data
-                if let Ok(game_info) = serde_json::from_value::<GameInfo>(data) {
-                    if sender.send(game_info).is_err() {
-                        println!("Failed to send game data - receiver likely dropped");
-                        break;
+        if config.has_changed().unwrap_or(false) {
+            let new_config = config.borrow_and_update().clone();
+            if new_config.live_client != live_client {
+                match LoLLiveClient::new(&new_config.live_client) {
+                    Ok(new_client) => {
+                        log::info!("Live Client settings changed, reconnecting");
+                        client = new_client;
+                        live_client = new_config.live_client;
+                    }
+                    Err(e) => log::warn!("Keeping the old Live Client settings: {:#}", e),
+                }
+            }
+            if new_config.poll != poll {
+                poll = new_config.poll;
+                backoff = Backoff::new(&poll);
+            }
+        }
+
+        let game_stats = match client.get_json(Endpoint::GameStats.path()).await {
+            Ok(game_stats) => game_stats,
+            Err(e) => {
+                let state = connection
+                    .state_for_error(&e)
+                    .or(connection.state())
+                    .unwrap_or(ConnectionState::NoClient);
+                if send_status(&sender, connection.update(state, Some(&e))).is_err() {
+                    break;
+                }
+
+                let delay = backoff.next_delay();
+                if matches!(state, ConnectionState::NoClient | ConnectionState::Loading) {
+                    log::info!(
+                        "No active game detected ({}). Retrying in {:.1}s",
+                        e,
+                        delay.as_secs_f64()
+                    );
+                    // Whatever game we were following is over, the next one starts a fresh log
+                    if cursor.reset() {
+                        let report = client.schema_report();
+                        if !report.is_empty() {
+                            log::warn!("{}", report);
+                        }
+                        if sender.send(GameUpdate::EventsReset).is_err() {
+                            break;
+                        }
+                    }
+                    if let Some(recorder) = recorder.as_mut() {
+                        recorder.start_session();
                     }
                 } else {
-                    println!("Failed to parse game data structure");
+                    log::warn!("Error fetching game stats: {}", e);
                 }
+                // A config change (new url, certificate) is worth trying straight away
+                tokio::select! {
+                    _ = tokio::time::sleep(delay) => {}
+                    Ok(()) = config.changed() => {}
+                }
+                continue;
             }
+        };
+
+        backoff.reset();
+        let mut game = FollowedGame {
+            client: &client,
+            sender: &sender,
+            poll: &poll,
+            config: &config,
+            cursor: &mut cursor,
+            connection: &mut connection,
+            recorder: recorder.as_mut(),
+            last_responses: HashMap::new(),
+            first: Some(GameInfo::default()),
+            has: Vec::new(),
+            spectating: false,
+            game_ended: false,
+            events: Vec::new(),
+        };
+        if game.follow(game_stats).await.is_err() {
+            log::debug!("Failed to send game data - receiver likely dropped");
+            break;
+        }
+    }
+
+    Ok(())
+}
+
+// Everything needed while a game is up, polling one endpoint at a time
+struct FollowedGame<'a> {
+    client: &'a LoLLiveClient,
+    sender: &'a mpsc::UnboundedSender<GameUpdate>,
+    poll: &'a PollConfig,
+    //Only checked for changes, the outer loop applies them
+    config: &'a watch::Receiver<FetcherConfig>,
+    cursor: &'a mut EventCursor,
+    connection: &'a mut ConnectionTracker,
+    recorder: Option<&'a mut Recorder>,
+    //What each endpoint said last, the same again isn't parsed or passed on
+    last_responses: HashMap<Endpoint, Value>,
+    //Put together from the first response of each endpoint and sent whole, None once it's gone out
+    first: Option<GameInfo>,
+    //Endpoints that have gone into `first` so far
+    has: Vec<Endpoint>,
+    //activeplayer answers with an error object, there's nobody to follow
+    spectating: bool,
+    //GameEnd has come through the event log
+    game_ended: bool,
+    //The whole event log so far, only kept while recording
+    events: Vec<Event>,
+}
+
+impl FollowedGame<'_> {
+    /// Polls until the game goes away or the config changes, Err only if the overlay stopped listening
+    async fn follow(
+        &mut self,
+        game_stats: Value,
+    ) -> Result<(), mpsc::error::SendError<GameUpdate>> {
+        let mut schedule = Schedule::new();
+        schedule.polled(Endpoint::GameStats, self.poll);
+        self.received(Endpoint::GameStats, game_stats)?;
+
+        loop {
+            if self.config.has_changed().unwrap_or(false) {
+                return Ok(());
+            }
+
+            let (endpoint, due) = schedule.next();
+            tokio::time::sleep_until(due).await;
+            if endpoint == Endpoint::Events {
+                let update = self.poll_events().await;
+                schedule.polled(endpoint, self.poll);
+                match update {
+                    Ok(update) => self.send_events(update)?,
+                    Err(e) => {
+                        if self.poll_failed(endpoint, &e)? {
+                            return Ok(());
+                        }
+                    }
+                }
+                continue;
+            }
+            let response = self.poll(endpoint).await;
+            schedule.polled(endpoint, self.poll);
+
+            let value = match response {
+                Ok(value) => value,
+                Err(e) => {
+                    if self.poll_failed(endpoint, &e)? {
+                        return Ok(());
+                    }
+                    continue;
+                }
+            };
+
+            // Spectators get an error object where the active player should be
+            if endpoint == Endpoint::ActivePlayer && value.get("error").is_some() {
+                self.spectating = true;
+                // Only kept for recordings, received() never sees it
+                self.last_responses.insert(endpoint, value);
+                send_status(self.sender, self.connection.update(self.state(), None))?;
+                // The rest of the first snapshot may have been waiting on it
+                self.send_first_if_whole()?;
+                continue;
+            }
+            if endpoint == Endpoint::ActivePlayer {
+                self.spectating = false;
+            }
+
+            if endpoint == Endpoint::GameStats {
+                self.record(&value);
+            }
+
+            self.received(endpoint, value)?;
+        }
+    }
+
+    // Parses what changed and sends just that, or holds on to it until the first snapshot is whole
+    fn received(
+        &mut self,
+        endpoint: Endpoint,
+        value: Value,
+    ) -> Result<(), mpsc::error::SendError<GameUpdate>> {
+        let state = self.state();
+        if self.last_responses.get(&endpoint) == Some(&value) {
+            return send_status(self.sender, self.connection.update(state, None));
+        }
+        self.last_responses.insert(endpoint, value.clone());
+
+        let update = match self.parse(endpoint, value) {
+            Ok(update) => update,
             Err(e) => {
-                println!("Error fetching game data: {}", e);
+                let status = self.connection.update(state, Some(&e));
+                if status.is_some() {
+                    log::warn!("Failed to parse {}: {}", endpoint.path(), e);
+                }
+                // Parsed again next time, even if it hasn't changed
+                self.last_responses.remove(&endpoint);
+                return send_status(self.sender, status);
+            }
+        };
+        send_status(self.sender, self.connection.update(state, None))?;
+
+        let Some(first) = self.first.as_mut() else {
+            return self.sender.send(update);
+        };
+        match update {
+            GameUpdate::ActivePlayer(active_player) => first.active_player = *active_player,
+            GameUpdate::Players(players) => first.all_players = players,
+            GameUpdate::GameData(game_data) => first.game_data = game_data,
+            _ => {}
+        }
+        if !self.has.contains(&endpoint) {
+            self.has.push(endpoint);
+        }
+        self.send_first_if_whole()
+    }
+
+    fn state(&self) -> ConnectionState {
+        if self.game_ended {
+            ConnectionState::PostGame
+        } else if self.spectating {
+            ConnectionState::Spectating
+        } else {
+            ConnectionState::InGame
+        }
+    }
+
+    // Spectators never get an active player, the first snapshot goes out without one
+    fn send_first_if_whole(&mut self) -> Result<(), mpsc::error::SendError<GameUpdate>> {
+        let whole = [
+            Endpoint::ActivePlayer,
+            Endpoint::PlayerList,
+            Endpoint::GameStats,
+        ]
+        .iter()
+        .filter(|endpoint| !(self.spectating && **endpoint == Endpoint::ActivePlayer))
+        .all(|endpoint| self.has.contains(endpoint));
+        match self.first.take_if(|_| whole) {
+            Some(game_info) => self.sender.send(GameUpdate::Info(Box::new(game_info))),
+            None => Ok(()),
+        }
+    }
+
+    // Recordings get an /allgamedata put together from the latest responses, at the /gamestats pace.
+    // Nothing until every endpoint has answered once, so the header knows who's playing
+    fn record(&mut self, game_stats: &Value) {
+        let Some(recorder) = self.recorder.as_mut() else {
+            return;
+        };
+        if self.first.is_some() {
+            return;
+        }
+        let mut all_game_data = json!({
+            "events": { "Events": self.events },
+            "gameData": game_stats,
+        });
+        for (endpoint, key) in [
+            (Endpoint::ActivePlayer, "activePlayer"),
+            (Endpoint::PlayerList, "allPlayers"),
+        ] {
+            if let Some(response) = self.last_responses.get(&endpoint) {
+                all_game_data[key] = response.clone();
             }
         }
+        if let Err(e) = recorder.record(&all_game_data) {
+            log::error!("Failed to record game data: {}", e);
+        }
+    }
 
-        tokio::time::sleep(tokio::time::Duration::from_millis(1000)).await;
+    fn parse(&self, endpoint: Endpoint, value: Value) -> Result<GameUpdate, LiveClientError> {
+        let path = endpoint.path();
+        Ok(match endpoint {
+            Endpoint::ActivePlayer => {
+                GameUpdate::ActivePlayer(Box::new(self.client.parse(path, value)?))
+            }
+            Endpoint::PlayerList => GameUpdate::Players(self.client.parse(path, value)?),
+            Endpoint::GameStats => GameUpdate::GameData(self.client.parse(path, value)?),
+            // Events have a cursor of their own, see poll_events
+            Endpoint::Events => unreachable!("events aren't polled as a part"),
+        })
     }
 
-    Ok(())
+    async fn poll(&self, endpoint: Endpoint) -> Result<Value, LiveClientError> {
+        self.client.get_json(endpoint.path()).await
+    }
+
+    // Only the end of the log, all of it again if it turns out to be a different game's
+    async fn poll_events(&mut self) -> Result<CursorUpdate, LiveClientError> {
+        let page = self
+            .client
+            .get_events_since(self.cursor.next_event_id())
+            .await?;
+        if let Some(events) = self.cursor.advance_page(page) {
+            return Ok(CursorUpdate::New(events));
+        }
+        let events = self.client.get_events_since(0).await?;
+        self.cursor.reset();
+        Ok(match self.cursor.advance(events) {
+            CursorUpdate::New(events) | CursorUpdate::Reset(events) => CursorUpdate::Reset(events),
+        })
+    }
+
+    fn send_events(
+        &mut self,
+        update: CursorUpdate,
+    ) -> Result<(), mpsc::error::SendError<GameUpdate>> {
+        if let CursorUpdate::Reset(_) = update {
+            self.game_ended = false;
+        }
+        let (CursorUpdate::New(events) | CursorUpdate::Reset(events)) = &update;
+        if self.recorder.is_some() {
+            if let CursorUpdate::Reset(_) = update {
+                self.events.clear();
+            }
+            self.events.extend(events.iter().cloned());
+        }
+        if has_game_ended(events) {
+            self.game_ended = true;
+            send_status(
+                self.sender,
+                self.connection.update(ConnectionState::PostGame, None),
+            )?;
+        }
+        send_events(self.sender, update)
+    }
+
+    /// Reports a poll that didn't go through, true if the game has gone away
+    fn poll_failed(
+        &mut self,
+        endpoint: Endpoint,
+        e: &LiveClientError,
+    ) -> Result<bool, mpsc::error::SendError<GameUpdate>> {
+        let state = self
+            .connection
+            .state_for_error(e)
+            .or(self.connection.state())
+            .unwrap_or(ConnectionState::InGame);
+        let status = self.connection.update(state, Some(e));
+        if status.is_some() {
+            log::warn!("Error fetching {}: {}", endpoint.path(), e);
+        }
+        send_status(self.sender, status)?;
+        Ok(matches!(
+            state,
+            ConnectionState::NoClient | ConnectionState::Loading
+        ))
+    }
+}
+
+fn has_game_ended(events: &[Event]) -> bool {
+    events
+        .iter()
+        .any(|event| matches!(event.kind, EventKind::GameEnd { .. }))
+}
+
+fn send_status(
+    sender: &mpsc::UnboundedSender<GameUpdate>,
+    status: Option<ConnectionStatus>,
+) -> Result<(), mpsc::error::SendError<GameUpdate>> {
+    match status {
+        Some(status) => sender.send(GameUpdate::Connection(status)),
+        None => Ok(()),
+    }
+}
+
+/// Sends a snapshot to the overlay, with only the events that are new since the last one
+pub fn send_snapshot(
+    sender: &mpsc::UnboundedSender<GameUpdate>,
+    cursor: &mut EventCursor,
+    mut game_info: GameInfo,
+) -> Result<(), mpsc::error::SendError<GameUpdate>> {
+    let events = std::mem::take(&mut game_info.events.events);
+    send_events(sender, cursor.advance(events))?;
+    sender.send(GameUpdate::Info(Box::new(game_info)))
+}
+
+fn send_events(
+    sender: &mpsc::UnboundedSender<GameUpdate>,
+    update: CursorUpdate,
+) -> Result<(), mpsc::error::SendError<GameUpdate>> {
+    match update {
+        CursorUpdate::New(events) if events.is_empty() => Ok(()),
+        CursorUpdate::New(events) => sender.send(GameUpdate::Events(events)),
+        CursorUpdate::Reset(events) => {
+            log::info!("Event log was reset, starting over");
+            sender.send(GameUpdate::EventsReset)?;
+            if events.is_empty() {
+                return Ok(());
+            }
+            sender.send(GameUpdate::Events(events))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::connection::ConnectionState;
+    use crate::mock::fixtures::Fixtures;
+    use crate::mock::server;
+    use crate::tls::CertTrust;
+    use axum_server::tls_rustls::RustlsConfig;
+    use flate2::read::MultiGzDecoder;
+    use std::io::BufRead;
+    use std::path::Path;
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    fn all_game_data() -> Value {
+        let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("fixtures/allgamedata.json");
+        serde_json::from_str(&std::fs::read_to_string(path).unwrap()).unwrap()
+    }
+
+    // The mock serving the fixtures from `dir` on a port of its own, returns the base url
+    async fn serve(dir: &Path) -> String {
+        std::fs::write(dir.join("allgamedata.json"), all_game_data().to_string()).unwrap();
+        let _ = rustls::crypto::ring::default_provider().install_default();
+        let cert = rcgen::generate_simple_self_signed(vec!["127.0.0.1".to_string()]).unwrap();
+        let tls = RustlsConfig::from_pem(
+            cert.serialize_pem().unwrap().into_bytes(),
+            cert.serialize_private_key_pem().into_bytes(),
+        )
+        .await
+        .unwrap();
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        let router = server::router(Arc::new(Fixtures::open(dir).unwrap()));
+        tokio::spawn(axum_server::from_tcp_rustls(listener, tls).serve(router.into_make_service()));
+        format!("https://{}/liveclientdata", addr)
+    }
+
+    // Runs the fetcher against `url` for half a second, every endpoint but /gamestats polled quickly
+    async fn fetch(
+        url: String,
+        recorder: Option<Recorder>,
+        game_stats: Duration,
+    ) -> Vec<GameUpdate> {
+        let mut live_client = LiveClientConfig {
+            url,
+            ..LiveClientConfig::default()
+        };
+        live_client.set_cert_trust(&CertTrust::Insecure);
+        let fast = Duration::from_millis(20);
+        let poll = PollConfig {
+            active_player: fast,
+            player_list: fast,
+            events: fast,
+            game_stats,
+            ..PollConfig::default()
+        };
+        let client = LoLLiveClient::new(&live_client).unwrap();
+        let (_config, config) = watch::channel(FetcherConfig { live_client, poll });
+        let (sender, mut receiver) = mpsc::unbounded_channel();
+        let fetcher = tokio::spawn(game_data_fetcher(sender, client, recorder, config));
+
+        let mut updates = Vec::new();
+        let deadline = tokio::time::Instant::now() + Duration::from_millis(500);
+        while let Ok(Some(update)) = tokio::time::timeout_at(deadline, receiver.recv()).await {
+            updates.push(update);
+        }
+        fetcher.abort();
+        updates
+    }
+
+    #[tokio::test]
+    async fn spectators_get_a_first_snapshot_and_stay_spectating() {
+        let dir = tempfile::tempdir().unwrap();
+        // What the game says on /activeplayer while spectating
+        std::fs::write(
+            dir.path().join("activeplayer.json"),
+            r#"{ "error": "Spectator mode doesn't currently support this feature" }"#,
+        )
+        .unwrap();
+        let updates = fetch(serve(dir.path()).await, None, Duration::from_millis(20)).await;
+
+        let first = updates
+            .iter()
+            .find_map(|update| match update {
+                GameUpdate::Info(info) => Some(info),
+                _ => None,
+            })
+            .expect("no first snapshot for a spectator");
+        assert!(!first.all_players.is_empty());
+        let states: Vec<ConnectionState> = updates
+            .iter()
+            .filter_map(|update| match update {
+                GameUpdate::Connection(status) => Some(status.state),
+                _ => None,
+            })
+            .collect();
+        let spectating = states
+            .iter()
+            .position(|state| *state == ConnectionState::Spectating)
+            .expect("never spectating");
+        assert_eq!(states[spectating..], [ConnectionState::Spectating]);
+    }
+
+    #[tokio::test]
+    async fn recordings_are_put_together_from_the_endpoints() {
+        let dir = tempfile::tempdir().unwrap();
+        let recording = dir.path().join("game.ndjson.gz");
+        let url = serve(dir.path()).await;
+        let recorder = Recorder::create(&recording).unwrap();
+        fetch(url, Some(recorder), Duration::from_millis(100)).await;
+
+        // The writer may not have finished the gzip member yet, every line up to there is readable
+        let read = || -> Vec<Value> {
+            let file = std::fs::File::open(&recording).unwrap();
+            std::io::BufReader::new(MultiGzDecoder::new(file))
+                .lines()
+                .map_while(Result::ok)
+                .map(|line| serde_json::from_str(&line).unwrap())
+                .collect()
+        };
+        let mut lines = read();
+        for _ in 0..100 {
+            if lines.len() > 2 {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(100)).await;
+            lines = read();
+        }
+        assert_eq!(lines[0]["type"], "header");
+        assert!(lines[0]["champion"].is_string());
+        assert!(lines.len() > 2);
+
+        let expected = all_game_data();
+        let data = &lines[1]["data"];
+        assert_eq!(lines[1]["type"], "snapshot");
+        for key in ["activePlayer", "allPlayers", "gameData"] {
+            assert_eq!(data[key], expected[key], "{}", key);
+        }
+        let events = |value: &Value| value["events"]["Events"].as_array().unwrap().len();
+        assert_eq!(events(&lines.last().unwrap()["data"]), events(&expected));
+    }
 }
@@ -1,6 +1,7 @@
-use crate::data::events::EventsWrapper;
+use crate::connection::ConnectionStatus;
+use crate::data::events::{Event, EventsWrapper};
 use crate::data::players::{ActivePlayer, Player};
-use ::serde::Deserialize;
+use ::serde::{Deserialize, Serialize};
 
 /*
 This will hold information about the game
@@ -10,18 +11,51 @@ It will be updated with more information as needed
 ///aatrox            "resourceType": "BLOODWELL",
 ///yone WIND  
 
-#[derive(Deserialize, Debug)]
-#[serde(rename_all = "camelCase")]
+#[derive(Deserialize, Serialize, Debug, Default)]
+#[serde(default, rename_all = "camelCase")]
 pub struct GameData {
+    /// "CLASSIC", "ARAM", ...
     pub game_mode: String,
+    /// Seconds since the game started
     pub game_time: f64,
+    /// "Map11" for Summoner's Rift, "Map12" for ARAM
+    pub map_name: String,
+    /// 11 for Summoner's Rift, 12 for ARAM
+    pub map_number: u32,
+    /// The elemental rift after the third dragon, "Default" before that
+    pub map_terrain: String,
 }
 
-#[derive(Deserialize, Debug)]
-#[serde(rename_all = "camelCase")]
+/// Everything /allgamedata returns
+#[derive(Deserialize, Serialize, Debug, Default)]
+#[serde(default, rename_all = "camelCase")]
 pub struct GameInfo {
+    /// The player whose client this is
     pub active_player: ActivePlayer,
+    /// Everyone in the game, both teams
     pub all_players: Vec<Player>,
+    /// The fetcher takes these out and sends them separately as GameUpdate::Events
     pub events: EventsWrapper,
+    /// The game itself, mode and time
     pub game_data: GameData,
 }
+
+/// Everything the fetcher sends to the overlay
+#[derive(Debug)]
+pub enum GameUpdate {
+    /// A whole snapshot, events already taken out. The live fetcher sends one when it finds a game,
+    /// then only the parts that changed
+    Info(Box<GameInfo>),
+    /// Latest /activeplayer, goes in GameInfo::active_player
+    ActivePlayer(Box<ActivePlayer>),
+    /// Latest /playerlist, goes in GameInfo::all_players
+    Players(Vec<Player>),
+    /// Latest /gamestats, goes in GameInfo::game_data
+    GameData(GameData),
+    /// Events that arrived since the last update
+    Events(Vec<Event>),
+    /// The event log started over (new game, reconnect), drop any events kept so far
+    EventsReset,
+    /// Where we're at with the League client (live source only)
+    Connection(ConnectionStatus),
+}
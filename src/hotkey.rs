@@ -1,30 +1,424 @@
-use anyhow::Result;
-use global_hotkey::{
-    GlobalHotKeyManager, HotKeyState,
-    hotkey::{Code, HotKey, Modifiers},
-};
+use crate::panels::PanelKind;
+use anyhow::{Context, Result, anyhow};
+use eframe::egui;
+use global_hotkey::{GlobalHotKeyEvent, GlobalHotKeyManager, HotKeyState, hotkey::HotKey};
+use serde::{Deserialize, Serialize};
+use std::str::FromStr;
+use std::sync::{Arc, Mutex};
 use tokio::sync::mpsc;
 
-pub fn setup_global_hotkey(sender: mpsc::UnboundedSender<()>) -> Result<GlobalHotKeyManager> {
-    let manager = GlobalHotKeyManager::new()?;
+/*
+Global hotkeys, set in the [hotkeys] section of the config as action = "binding",
+plus a hotkey per panel window in [windows.<panel>] that shows/hides just that window.
+Bindings are anything global-hotkey can parse ("Ctrl+Shift+X", "Alt+F9", "Ctrl+Alt+S"),
+an empty one leaves the action without a hotkey. Two actions can't share a binding.
+The replay_* ones are only registered while a recording is playing (--replay), so they don't take
+keys away from the game otherwise.
+Presses arrive on global-hotkey's own thread and are handed to the overlay as a HotkeyAction.
+*/
 
-    // Create Ctrl+Shift+X hotkey
-    let hotkey = HotKey::new(Some(Modifiers::CONTROL | Modifiers::SHIFT), Code::KeyX);
-    manager.register(hotkey)?;
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HotkeyAction {
+    ToggleOverlay,
+    OpenSettings,
+    CyclePanel,
+    ToggleInteractive,
+    MarkHighlight,
+    EditLayout,
+    ReplayPause,
+    ReplaySlower,
+    ReplayFaster,
+    ReplayBack,
+    ReplayForward,
+    TogglePanel(PanelKind),
+}
+
+impl HotkeyAction {
+    /// The ones in [hotkeys], panel windows have theirs in [windows.<panel>]
+    pub const GLOBAL: [HotkeyAction; 11] = [
+        HotkeyAction::ToggleOverlay,
+        HotkeyAction::OpenSettings,
+        HotkeyAction::CyclePanel,
+        HotkeyAction::ToggleInteractive,
+        HotkeyAction::MarkHighlight,
+        HotkeyAction::EditLayout,
+        HotkeyAction::ReplayPause,
+        HotkeyAction::ReplaySlower,
+        HotkeyAction::ReplayFaster,
+        HotkeyAction::ReplayBack,
+        HotkeyAction::ReplayForward,
+    ];
 
-    // Spawn a background thread to listen for hotkey events
-    std::thread::spawn(move || {
-        use global_hotkey::GlobalHotKeyEvent;
+    /// Only does anything while a recording is playing
+    pub fn is_replay(&self) -> bool {
+        matches!(
+            self,
+            HotkeyAction::ReplayPause
+                | HotkeyAction::ReplaySlower
+                | HotkeyAction::ReplayFaster
+                | HotkeyAction::ReplayBack
+                | HotkeyAction::ReplayForward
+        )
+    }
+
+    /// Where it's set in the config
+    pub fn config_key(&self) -> String {
+        match self {
+            HotkeyAction::ToggleOverlay => "hotkeys.toggle_overlay".to_string(),
+            HotkeyAction::OpenSettings => "hotkeys.open_settings".to_string(),
+            HotkeyAction::CyclePanel => "hotkeys.cycle_panel".to_string(),
+            HotkeyAction::ToggleInteractive => "hotkeys.toggle_interactive".to_string(),
+            HotkeyAction::MarkHighlight => "hotkeys.mark_highlight".to_string(),
+            HotkeyAction::EditLayout => "hotkeys.edit_layout".to_string(),
+            HotkeyAction::ReplayPause => "hotkeys.replay_pause".to_string(),
+            HotkeyAction::ReplaySlower => "hotkeys.replay_slower".to_string(),
+            HotkeyAction::ReplayFaster => "hotkeys.replay_faster".to_string(),
+            HotkeyAction::ReplayBack => "hotkeys.replay_back".to_string(),
+            HotkeyAction::ReplayForward => "hotkeys.replay_forward".to_string(),
+            HotkeyAction::TogglePanel(kind) => format!("windows.{}.hotkey", kind.name()),
+        }
+    }
+
+    pub fn description(&self) -> String {
+        match self {
+            HotkeyAction::ToggleOverlay => "Show/hide the overlay".to_string(),
+            HotkeyAction::OpenSettings => "Open/close settings".to_string(),
+            HotkeyAction::CyclePanel => "Show one panel at a time".to_string(),
+            HotkeyAction::ToggleInteractive => "Let the overlay take clicks".to_string(),
+            HotkeyAction::MarkHighlight => "Mark a highlight".to_string(),
+            HotkeyAction::EditLayout => "Move/resize the panels".to_string(),
+            HotkeyAction::ReplayPause => "Pause/resume the replay".to_string(),
+            HotkeyAction::ReplaySlower => "Replay at half the speed".to_string(),
+            HotkeyAction::ReplayFaster => "Replay at double the speed".to_string(),
+            HotkeyAction::ReplayBack => "Replay 30s back".to_string(),
+            HotkeyAction::ReplayForward => "Replay 30s on".to_string(),
+            HotkeyAction::TogglePanel(kind) => format!("Show/hide {}", kind.title()),
+        }
+    }
+}
 
-        loop {
-            if let Ok(event) = GlobalHotKeyEvent::receiver().try_recv() {
-                if event.state == HotKeyState::Pressed {
-                    let _ = sender.send(());
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct HotkeyConfig {
+    #[serde(alias = "toggle")]
+    pub toggle_overlay: String,
+    #[serde(alias = "settings")]
+    pub open_settings: String,
+    pub cycle_panel: String,
+    pub toggle_interactive: String,
+    pub mark_highlight: String,
+    pub edit_layout: String,
+    pub replay_pause: String,
+    pub replay_slower: String,
+    pub replay_faster: String,
+    pub replay_back: String,
+    pub replay_forward: String,
+}
+
+impl Default for HotkeyConfig {
+    fn default() -> Self {
+        Self {
+            toggle_overlay: "Ctrl+Shift+X".to_string(),
+            // Hotkeys are grabbed system wide, so the rest are left for the user to bind. The menu
+            // (where they're bound) gets a chord nothing else uses, it'd be out of reach otherwise
+            open_settings: "Ctrl+Alt+F9".to_string(),
+            cycle_panel: String::new(),
+            toggle_interactive: String::new(),
+            mark_highlight: String::new(),
+            edit_layout: String::new(),
+            replay_pause: String::new(),
+            replay_slower: String::new(),
+            replay_faster: String::new(),
+            replay_back: String::new(),
+            replay_forward: String::new(),
+        }
+    }
+}
+
+impl HotkeyConfig {
+    /// Empty for TogglePanel, those live with their window
+    pub fn binding(&self, action: HotkeyAction) -> &str {
+        match action {
+            HotkeyAction::ToggleOverlay => &self.toggle_overlay,
+            HotkeyAction::OpenSettings => &self.open_settings,
+            HotkeyAction::CyclePanel => &self.cycle_panel,
+            HotkeyAction::ToggleInteractive => &self.toggle_interactive,
+            HotkeyAction::MarkHighlight => &self.mark_highlight,
+            HotkeyAction::EditLayout => &self.edit_layout,
+            HotkeyAction::ReplayPause => &self.replay_pause,
+            HotkeyAction::ReplaySlower => &self.replay_slower,
+            HotkeyAction::ReplayFaster => &self.replay_faster,
+            HotkeyAction::ReplayBack => &self.replay_back,
+            HotkeyAction::ReplayForward => &self.replay_forward,
+            HotkeyAction::TogglePanel(_) => "",
+        }
+    }
+
+    pub fn binding_mut(&mut self, action: HotkeyAction) -> Option<&mut String> {
+        match action {
+            HotkeyAction::ToggleOverlay => Some(&mut self.toggle_overlay),
+            HotkeyAction::OpenSettings => Some(&mut self.open_settings),
+            HotkeyAction::CyclePanel => Some(&mut self.cycle_panel),
+            HotkeyAction::ToggleInteractive => Some(&mut self.toggle_interactive),
+            HotkeyAction::MarkHighlight => Some(&mut self.mark_highlight),
+            HotkeyAction::EditLayout => Some(&mut self.edit_layout),
+            HotkeyAction::ReplayPause => Some(&mut self.replay_pause),
+            HotkeyAction::ReplaySlower => Some(&mut self.replay_slower),
+            HotkeyAction::ReplayFaster => Some(&mut self.replay_faster),
+            HotkeyAction::ReplayBack => Some(&mut self.replay_back),
+            HotkeyAction::ReplayForward => Some(&mut self.replay_forward),
+            HotkeyAction::TogglePanel(_) => None,
+        }
+    }
+}
+
+/// Bindings that don't parse or clash, empty if everything is fine
+pub fn problems(bindings: &[(HotkeyAction, String)]) -> Vec<String> {
+    let mut problems = Vec::new();
+    let mut parsed: Vec<(HotKey, HotkeyAction)> = Vec::new();
+    for (action, binding) in bindings {
+        match HotKey::from_str(binding) {
+            Ok(hotkey) => {
+                if let Some((_, taken_by)) = parsed.iter().find(|(other, _)| *other == hotkey) {
+                    problems.push(format!(
+                        "{}: {} is already used by {}",
+                        action.config_key(),
+                        binding,
+                        taken_by.config_key()
+                    ));
                 }
+                parsed.push((hotkey, *action));
             }
-            std::thread::sleep(std::time::Duration::from_millis(100));
+            Err(e) => problems.push(format!("{}: {}", action.config_key(), e)),
         }
-    });
+    }
+    problems
+}
+
+fn parse(
+    bindings: &[(HotkeyAction, String)],
+    replaying: bool,
+) -> Result<Vec<(HotKey, HotkeyAction)>> {
+    if let Some(problem) = problems(bindings).into_iter().next() {
+        return Err(anyhow!(problem));
+    }
+    Ok(bindings
+        .iter()
+        .filter(|(action, _)| replaying || !action.is_replay())
+        .filter_map(|(action, binding)| Some((HotKey::from_str(binding).ok()?, *action)))
+        .collect())
+}
+
+/// The registered hotkeys. Have to be made on the main thread, and can be rebound when the config changes
+pub struct Hotkeys {
+    manager: GlobalHotKeyManager,
+    //Shared with the event handler so it knows what a press means
+    bound: Arc<Mutex<Vec<(HotKey, HotkeyAction)>>>,
+    //Whether the replay_* bindings get registered
+    replaying: bool,
+    //Woken on every press, the overlay may not be repainting by itself
+    repaint: Arc<Mutex<Option<egui::Context>>>,
+}
+
+impl Hotkeys {
+    pub fn new(
+        bindings: &[(HotkeyAction, String)],
+        replaying: bool,
+        sender: mpsc::UnboundedSender<HotkeyAction>,
+    ) -> Result<Self> {
+        let manager = GlobalHotKeyManager::new()?;
+
+        let bindings = parse(bindings, replaying)?;
+        register(&manager, &bindings)?;
+        let bound = Arc::new(Mutex::new(bindings));
+        let repaint = Arc::new(Mutex::new(None));
+
+        // Called on global-hotkey's thread for every press, no need for a thread of our own
+        let handler_bound = bound.clone();
+        let handler_repaint = repaint.clone();
+        GlobalHotKeyEvent::set_event_handler(Some(move |event: GlobalHotKeyEvent| {
+            dispatch(event, &handler_bound, &sender, &handler_repaint);
+        }));
+
+        Ok(Self {
+            manager,
+            bound,
+            replaying,
+            repaint,
+        })
+    }
+
+    /// Repaints `ctx` on every press, so a hotkey is handled even while nothing else is going on
+    pub fn wake(&self, ctx: &egui::Context) {
+        if let Ok(mut repaint) = self.repaint.lock() {
+            *repaint = Some(ctx.clone());
+        }
+    }
+
+    pub fn rebind(&mut self, bindings: &[(HotkeyAction, String)]) -> Result<()> {
+        let bindings = parse(bindings, self.replaying)?;
+        let mut bound = self
+            .bound
+            .lock()
+            .map_err(|_| anyhow!("Hotkey handler crashed"))?;
+        if *bound == bindings {
+            return Ok(());
+        }
+
+        unregister(&self.manager, &bound);
+        if let Err(e) = register(&self.manager, &bindings) {
+            // Don't end up with no hotkeys at all
+            let _ = register(&self.manager, &bound);
+            return Err(e);
+        }
+        for (hotkey, action) in &bindings {
+            println!("{} is now {}", action.config_key(), hotkey);
+        }
+        *bound = bindings;
+        Ok(())
+    }
+}
+
+// Passes a press on to the overlay and wakes it up to handle it
+fn dispatch(
+    event: GlobalHotKeyEvent,
+    bound: &Mutex<Vec<(HotKey, HotkeyAction)>>,
+    sender: &mpsc::UnboundedSender<HotkeyAction>,
+    repaint: &Mutex<Option<egui::Context>>,
+) {
+    if event.state != HotKeyState::Pressed {
+        return;
+    }
+    let action = match bound.lock() {
+        Ok(bound) => bound
+            .iter()
+            .find(|(key, _)| key.id() == event.id)
+            .map(|(_, action)| *action),
+        Err(_) => None,
+    };
+    if let Some(action) = action
+        && sender.send(action).is_ok()
+        && let Ok(repaint) = repaint.lock()
+        && let Some(ctx) = repaint.as_ref()
+    {
+        ctx.request_repaint();
+    }
+}
+
+// All or nothing, a half registered set gets undone
+fn register(manager: &GlobalHotKeyManager, bindings: &[(HotKey, HotkeyAction)]) -> Result<()> {
+    for (i, (hotkey, action)) in bindings.iter().enumerate() {
+        if let Err(e) = manager.register(*hotkey) {
+            unregister(manager, &bindings[..i]);
+            return Err(e).with_context(|| {
+                format!(
+                    "Couldn't register {} for {}, another program may already be using it",
+                    hotkey,
+                    action.config_key()
+                )
+            });
+        }
+    }
+    Ok(())
+}
+
+fn unregister(manager: &GlobalHotKeyManager, bindings: &[(HotKey, HotkeyAction)]) {
+    for (hotkey, _) in bindings {
+        let _ = manager.unregister(*hotkey);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn bindings(keys: &[(HotkeyAction, &str)]) -> Vec<(HotkeyAction, String)> {
+        keys.iter()
+            .map(|(action, key)| (*action, key.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn only_toggle_and_the_menu_are_bound_by_default() {
+        let defaults = crate::config::Config::default().hotkey_bindings();
+        assert!(problems(&defaults).is_empty());
+        let bound: Vec<HotkeyAction> = defaults.iter().map(|(action, _)| *action).collect();
+        assert_eq!(
+            bound,
+            [HotkeyAction::ToggleOverlay, HotkeyAction::OpenSettings]
+        );
+    }
+
+    #[test]
+    fn replay_keys_are_only_registered_while_replaying() {
+        let bindings = bindings(&[
+            (HotkeyAction::ToggleOverlay, "Ctrl+Shift+X"),
+            (HotkeyAction::ReplayPause, "Ctrl+Alt+F5"),
+        ]);
+        let actions = |replaying| -> Vec<HotkeyAction> {
+            parse(&bindings, replaying)
+                .unwrap()
+                .into_iter()
+                .map(|(_, action)| action)
+                .collect()
+        };
+        assert_eq!(actions(false), [HotkeyAction::ToggleOverlay]);
+        assert_eq!(
+            actions(true),
+            [HotkeyAction::ToggleOverlay, HotkeyAction::ReplayPause]
+        );
+    }
+
+    #[test]
+    fn a_press_wakes_the_overlay_up() {
+        let ctx = egui::Context::default();
+        let woken = Arc::new(std::sync::atomic::AtomicUsize::new(0));
+        let counter = woken.clone();
+        ctx.set_request_repaint_callback(move |_| {
+            counter.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+        });
+        let toggle = HotKey::from_str("Ctrl+Shift+X").unwrap();
+        let bound = Mutex::new(vec![(toggle, HotkeyAction::ToggleOverlay)]);
+        let repaint = Mutex::new(Some(ctx));
+        let (sender, mut receiver) = mpsc::unbounded_channel();
+
+        let press = |state| GlobalHotKeyEvent {
+            id: toggle.id(),
+            state,
+        };
+        dispatch(press(HotKeyState::Released), &bound, &sender, &repaint);
+        assert!(receiver.try_recv().is_err());
+        assert_eq!(woken.load(std::sync::atomic::Ordering::SeqCst), 0);
+
+        dispatch(press(HotKeyState::Pressed), &bound, &sender, &repaint);
+        assert_eq!(receiver.try_recv(), Ok(HotkeyAction::ToggleOverlay));
+        assert_eq!(woken.load(std::sync::atomic::Ordering::SeqCst), 1);
+    }
+
+    #[test]
+    fn a_key_bound_twice_is_reported_once_per_extra_use() {
+        let bindings = bindings(&[
+            (HotkeyAction::ToggleOverlay, "Ctrl+Shift+X"),
+            (HotkeyAction::OpenSettings, "shift+ctrl+x"),
+            (HotkeyAction::CyclePanel, "Ctrl+Shift+X"),
+        ]);
+        assert_eq!(
+            problems(&bindings),
+            [
+                "hotkeys.open_settings: shift+ctrl+x is already used by hotkeys.toggle_overlay",
+                "hotkeys.cycle_panel: Ctrl+Shift+X is already used by hotkeys.toggle_overlay",
+            ]
+        );
+    }
 
-    Ok(manager)
+    #[test]
+    fn a_binding_that_doesnt_parse_is_reported() {
+        let bindings = bindings(&[
+            (HotkeyAction::ToggleOverlay, "Ctrl+Shift+Nope"),
+            (HotkeyAction::OpenSettings, "Ctrl+Alt+F9"),
+        ]);
+        let problems = problems(&bindings);
+        assert_eq!(problems.len(), 1);
+        assert!(problems[0].starts_with("hotkeys.toggle_overlay: "));
+    }
 }
@@ -0,0 +1,241 @@
+use anyhow::{Context, Result, bail};
+use eframe::egui;
+use egui::{ColorImage, TextureHandle, TextureOptions};
+use league_overlay::dragontail::Dragontail;
+use serde::{Deserialize, Serialize};
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, mpsc};
+
+/*
+Icons for the panels (stat rows, champions, items, summoner spells, runes) as egui textures.
+Game icons come from the dragontail bundle when one is loaded (see dragontail.rs), everything else
+from the folder in the [icons] section of the config, laid out like a dragontail img folder:
+    <icons>/stats/<row image>.png     e.g. stats/attack_damage.png
+    <icons>/champion/Ahri.png  item/3031.png  spell/SummonerFlash.png  rune/8112.png
+PNGs are decoded on a thread of their own and uploaded the next frame, until then (or when
+there's no file for it) whoever asked draws its emoji or text instead.
+*/
+
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct IconsConfig {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub path: Option<PathBuf>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Icon {
+    //A stat row's image, the file name without .png
+    Stat(String),
+    //By the name the Live Client uses
+    Champion(String),
+    Item(u32),
+    //By its id, "SummonerFlash" (see SummonerSpell::id)
+    SummonerSpell(String),
+    Rune(u32),
+}
+
+enum Slot {
+    Loading,
+    Loaded(TextureHandle),
+    Missing,
+}
+
+struct Request {
+    icon: Icon,
+    path: PathBuf,
+    generation: u64,
+    ctx: egui::Context,
+}
+
+struct Decoded {
+    icon: Icon,
+    generation: u64,
+    image: Option<ColorImage>,
+}
+
+pub struct IconCache {
+    dir: Option<PathBuf>,
+    dragontail: Option<Arc<Dragontail>>,
+    //Bumped when the sources change, so images decoded for the old ones are dropped
+    generation: u64,
+    //Asked for from &self while drawing, hence the RefCell
+    slots: RefCell<HashMap<Icon, Slot>>,
+    requests: mpsc::Sender<Request>,
+    decoded: mpsc::Receiver<Decoded>,
+}
+
+impl IconCache {
+    pub fn new() -> Self {
+        let (requests, request_receiver) = mpsc::channel::<Request>();
+        let (decoded_sender, decoded) = mpsc::channel();
+        std::thread::spawn(move || {
+            while let Ok(request) = request_receiver.recv() {
+                let image = match decode(&request.path) {
+                    Ok(image) => Some(image),
+                    Err(e) => {
+                        // A missing file is just an icon we don't have, anything else is worth a line
+                        if request.path.exists() {
+                            println!("Couldn't load icon {}: {:#}", request.path.display(), e);
+                        }
+                        None
+                    }
+                };
+                let decoded = Decoded {
+                    icon: request.icon,
+                    generation: request.generation,
+                    image,
+                };
+                if decoded_sender.send(decoded).is_err() {
+                    return;
+                }
+                request.ctx.request_repaint();
+            }
+        });
+        Self {
+            dir: None,
+            dragontail: None,
+            generation: 0,
+            slots: RefCell::new(HashMap::new()),
+            requests,
+            decoded,
+        }
+    }
+
+    /// Where icons come from, everything loaded so far is dropped if that changed
+    pub fn set_sources(&mut self, dir: Option<PathBuf>, dragontail: Option<Arc<Dragontail>>) {
+        let same_dragontail = match (&self.dragontail, &dragontail) {
+            (Some(old), Some(new)) => Arc::ptr_eq(old, new),
+            (old, new) => old.is_none() && new.is_none(),
+        };
+        if self.dir == dir && same_dragontail {
+            return;
+        }
+        self.dir = dir;
+        self.dragontail = dragontail;
+        self.generation += 1;
+        self.slots.get_mut().clear();
+    }
+
+    /// Uploads whatever the decoder has finished, once a frame before anything is drawn
+    pub fn poll(&mut self, ctx: &egui::Context) {
+        while let Ok(decoded) = self.decoded.try_recv() {
+            if decoded.generation != self.generation {
+                continue;
+            }
+            let slot = match decoded.image {
+                Some(image) => Slot::Loaded(ctx.load_texture(
+                    format!("icon {:?}", decoded.icon),
+                    image,
+                    TextureOptions::LINEAR,
+                )),
+                None => Slot::Missing,
+            };
+            self.slots.get_mut().insert(decoded.icon, slot);
+        }
+    }
+
+    /// The texture if it's loaded, asks for it the first time
+    pub fn get(&self, ctx: &egui::Context, icon: &Icon) -> Option<TextureHandle> {
+        let mut slots = self.slots.borrow_mut();
+        match slots.get(icon) {
+            Some(Slot::Loaded(texture)) => return Some(texture.clone()),
+            Some(Slot::Loading | Slot::Missing) => return None,
+            None => {}
+        }
+        let slot = match self.path(icon) {
+            Some(path) => {
+                let request = Request {
+                    icon: icon.clone(),
+                    path,
+                    generation: self.generation,
+                    ctx: ctx.clone(),
+                };
+                match self.requests.send(request) {
+                    Ok(()) => Slot::Loading,
+                    Err(_) => Slot::Missing,
+                }
+            }
+            None => Slot::Missing,
+        };
+        slots.insert(icon.clone(), slot);
+        None
+    }
+
+    /// Draws the icon at size x size, false if there's nothing to draw (yet)
+    pub fn show(&self, ui: &mut egui::Ui, icon: &Icon, size: f32) -> bool {
+        match self.get(ui.ctx(), icon) {
+            Some(texture) => {
+                ui.add(egui::Image::new(&texture).fit_to_exact_size(egui::vec2(size, size)));
+                true
+            }
+            None => false,
+        }
+    }
+
+    fn path(&self, icon: &Icon) -> Option<PathBuf> {
+        if let Some(dragontail) = &self.dragontail {
+            let path = match icon {
+                Icon::Stat(_) => None,
+                Icon::Champion(name) => dragontail
+                    .champion(name)
+                    .map(|champion| dragontail.image_path(&champion.image)),
+                Icon::Item(id) => dragontail
+                    .item(*id)
+                    .map(|item| dragontail.image_path(&item.image)),
+                Icon::SummonerSpell(id) => dragontail
+                    .summoner_spell_named(id)
+                    .map(|spell| dragontail.image_path(&spell.image)),
+                Icon::Rune(id) => dragontail
+                    .rune(*id)
+                    .map(|rune| dragontail.rune_icon_path(rune)),
+            };
+            if path.is_some() {
+                return path;
+            }
+        }
+        let dir = self.dir.as_ref()?;
+        let (folder, name) = match icon {
+            Icon::Stat(name) => ("stats", name.clone()),
+            // Without dragontail the best guess at its id, "Lee Sin" is LeeSin.png
+            Icon::Champion(name) => (
+                "champion",
+                name.chars().filter(|c| c.is_alphanumeric()).collect(),
+            ),
+            Icon::Item(id) => ("item", id.to_string()),
+            Icon::SummonerSpell(id) => ("spell", id.clone()),
+            Icon::Rune(id) => ("rune", id.to_string()),
+        };
+        Some(dir.join(folder).join(format!("{}.png", name)))
+    }
+}
+
+fn decode(path: &Path) -> Result<ColorImage> {
+    let file = std::fs::File::open(path).context("can't open it")?;
+    let mut decoder = png::Decoder::new(std::io::BufReader::new(file));
+    // Palettes and bit depths other than 8 come out as plain 8 bit gray/rgb(a)
+    decoder.set_transformations(png::Transformations::normalize_to_color8());
+    let mut reader = decoder.read_info().context("not a PNG")?;
+    let mut buf = vec![0; reader.output_buffer_size()];
+    let info = reader.next_frame(&mut buf).context("not a PNG")?;
+    let size = [info.width as usize, info.height as usize];
+    let pixels = &buf[..info.buffer_size()];
+    Ok(match info.color_type {
+        png::ColorType::Rgba => ColorImage::from_rgba_unmultiplied(size, pixels),
+        png::ColorType::Rgb => ColorImage::from_rgb(size, pixels),
+        png::ColorType::Grayscale => ColorImage::from_gray(size, pixels),
+        png::ColorType::GrayscaleAlpha => {
+            let rgba: Vec<u8> = pixels
+                .chunks_exact(2)
+                .flat_map(|gray_alpha| {
+                    let [gray, alpha] = [gray_alpha[0], gray_alpha[1]];
+                    [gray, gray, gray, alpha]
+                })
+                .collect();
+            ColorImage::from_rgba_unmultiplied(size, &rgba)
+        }
+        other => bail!("unsupported PNG color type {:?}", other),
+    })
+}
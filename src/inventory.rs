@@ -0,0 +1,122 @@
+use crate::config::PanelConfig;
+use crate::icons::{Icon, IconCache};
+use eframe::egui;
+use egui::{Align2, Color32, FontId, RichText, Sense, vec2};
+use league_overlay::data::items::Item;
+
+/*
+A player's items as a strip of slots: the six inventory slots in the order the game has them,
+then the trinket a little apart, and what it's all worth at the end.
+Each slot shows the item's icon (see icons.rs), or the start of its name without one, and how many
+there are for stacks like potions and control wards. Hovering a slot shows its name and price,
+which only works while the overlay takes the mouse (the menu, toggle_interactive or layout editing).
+The stats panel shows yours, the enemies and scoreboard panels show others' when they're clicked.
+*/
+
+const INVENTORY_SLOTS: usize = 6;
+//The Live Client's slot for the trinket, right after the inventory
+const TRINKET_SLOT: usize = 6;
+const SLOT_SIZE: f32 = 26.0;
+
+/// Slot 0 to 5 and then the trinket, None for an empty one
+pub fn slots(items: &[Item]) -> [Option<&Item>; INVENTORY_SLOTS + 1] {
+    let mut slots = [None; INVENTORY_SLOTS + 1];
+    for item in items {
+        if let Some(slot) = slots.get_mut(item.slot as usize) {
+            *slot = Some(item);
+        }
+    }
+    slots
+}
+
+/// What everything in the inventory cost, stacks count once per item in them
+pub fn total_value(items: &[Item]) -> u32 {
+    items
+        .iter()
+        .map(|item| item.price * item.count.max(1) as u32)
+        .sum()
+}
+
+pub fn render(ui: &mut egui::Ui, panel: &PanelConfig, items: &[Item], icons: &IconCache) {
+    ui.horizontal(|ui| {
+        ui.spacing_mut().item_spacing.x = 2.0;
+        for (i, item) in slots(items).into_iter().enumerate() {
+            if i == TRINKET_SLOT {
+                ui.add_space(6.0);
+            }
+            render_slot(ui, panel, item, icons);
+        }
+        ui.add_space(6.0);
+        ui.colored_label(
+            panel.value_color.0,
+            RichText::new(format!("{}g", total_value(items))).strong(),
+        );
+    });
+}
+
+fn render_slot(ui: &mut egui::Ui, panel: &PanelConfig, item: Option<&Item>, icons: &IconCache) {
+    let (rect, response) = ui.allocate_exact_size(vec2(SLOT_SIZE, SLOT_SIZE), Sense::hover());
+    let painter = ui.painter();
+    painter.rect_filled(rect, 3.0, Color32::from_black_alpha(160));
+    let Some(item) = item else {
+        return;
+    };
+    match icons.get(ui.ctx(), &Icon::Item(item.id)) {
+        Some(texture) => {
+            egui::Image::new(&texture).paint_at(ui, rect.shrink(1.0));
+        }
+        None => {
+            let short: String = item.name.chars().take(3).collect();
+            painter.text(
+                rect.center(),
+                Align2::CENTER_CENTER,
+                short,
+                FontId::proportional(9.0),
+                panel.label_color.0,
+            );
+        }
+    }
+    if item.count > 1 {
+        let corner = rect.right_bottom() - vec2(2.0, 1.0);
+        // Dark outline so it reads over any icon
+        for offset in [vec2(1.0, 1.0), vec2(-1.0, -1.0)] {
+            painter.text(
+                corner + offset,
+                Align2::RIGHT_BOTTOM,
+                item.count.to_string(),
+                FontId::proportional(11.0),
+                Color32::BLACK,
+            );
+        }
+        painter.text(
+            corner,
+            Align2::RIGHT_BOTTOM,
+            item.count.to_string(),
+            FontId::proportional(11.0),
+            Color32::WHITE,
+        );
+    }
+    response.on_hover_ui(|ui| {
+        ui.label(RichText::new(&item.name).strong());
+        if item.count > 1 {
+            ui.label(format!(
+                "{}g each, {}g for {}",
+                item.price,
+                item.price * item.count as u32,
+                item.count
+            ));
+        } else {
+            ui.label(format!("{}g", item.price));
+        }
+    });
+}
+
+/// Whether someone's items were asked for, clicking `response` flips it
+pub fn expanded(ui: &egui::Ui, response: &egui::Response, id: egui::Id) -> bool {
+    let mut expanded = ui.data(|data| data.get_temp::<bool>(id).unwrap_or(false));
+    if response.clicked() {
+        expanded = !expanded;
+        ui.data_mut(|data| data.insert_temp(id, expanded));
+    }
+    expanded
+}
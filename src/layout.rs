@@ -0,0 +1,103 @@
+use crate::config::{self, WindowsConfig};
+use crate::panels::PanelKind;
+use crate::placement::Placement;
+use anyhow::Result;
+use eframe::egui;
+use egui::{Align2, Color32, FontId, Rect, Sense, Stroke, vec2};
+use std::collections::HashMap;
+use std::path::Path;
+
+/*
+The edit layout mode, toggled with the edit_layout hotkey (unbound by default).
+Every panel shows up with a handle over it and takes the mouse: dragging it moves the window,
+dragging the corner grip resizes it. The OS does the actual moving (StartDrag/BeginResize), we just
+note where each window is every frame. Toggling the mode off turns those spots into anchors and
+offsets (see Placement::from_rect) and saves them to [windows.<panel>] in the config file.
+*/
+
+const HANDLE_COLOR: Color32 = Color32::from_rgb(255, 200, 0);
+//Side of the resize grip in the bottom right corner, in points
+const GRIP_SIZE: f32 = 18.0;
+
+#[derive(Default)]
+pub struct LayoutEditor {
+    //Where each panel's window was last seen, in points from the top left of the primary monitor
+    rects: HashMap<PanelKind, Rect>,
+}
+
+impl LayoutEditor {
+    /// Draws the handle over the viewport `ctx` belongs to, call it after the panel itself
+    pub fn handles(&mut self, ctx: &egui::Context, kind: PanelKind) {
+        let (outer, inner) = ctx.input(|i| (i.viewport().outer_rect, i.viewport().inner_rect));
+        if let Some(outer) = outer
+            && let Some(inner) = inner
+        {
+            self.rects
+                .insert(kind, Rect::from_min_size(outer.min, inner.size()));
+        }
+
+        let screen = ctx.screen_rect();
+        egui::Area::new(egui::Id::new(("layout_handle", kind.name())))
+            .fixed_pos(screen.min)
+            .order(egui::Order::Foreground)
+            .show(ctx, |ui| {
+                let window = ui.allocate_rect(screen, Sense::drag());
+                let grip = Rect::from_min_max(screen.max - vec2(GRIP_SIZE, GRIP_SIZE), screen.max);
+                let grip = ui.interact(grip, ui.id().with("grip"), Sense::drag());
+                if grip.drag_started() {
+                    ctx.send_viewport_cmd(egui::ViewportCommand::BeginResize(
+                        egui::ResizeDirection::SouthEast,
+                    ));
+                } else if window.drag_started() {
+                    ctx.send_viewport_cmd(egui::ViewportCommand::StartDrag);
+                }
+                if grip.hovered() {
+                    ctx.set_cursor_icon(egui::CursorIcon::ResizeSouthEast);
+                } else if window.hovered() {
+                    ctx.set_cursor_icon(egui::CursorIcon::Move);
+                }
+
+                let painter = ui.painter();
+                painter.rect_filled(screen, 8.0, Color32::from_black_alpha(90));
+                painter.rect_stroke(
+                    screen.shrink(1.0),
+                    8.0,
+                    Stroke::new(2.0, HANDLE_COLOR),
+                    egui::StrokeKind::Inside,
+                );
+                painter.text(
+                    screen.center(),
+                    Align2::CENTER_CENTER,
+                    format!("✥ {}", kind.title()),
+                    FontId::proportional(16.0),
+                    HANDLE_COLOR,
+                );
+                painter.text(
+                    screen.max - vec2(3.0, 3.0),
+                    Align2::RIGHT_BOTTOM,
+                    "◢",
+                    FontId::proportional(GRIP_SIZE),
+                    HANDLE_COLOR,
+                );
+            });
+    }
+
+    /// Writes where the panels ended up into `windows`
+    pub fn apply(&self, monitor: Rect, windows: &mut WindowsConfig) {
+        for (kind, rect) in &self.rects {
+            let placement = Placement::from_rect(monitor, *rect);
+            let window = windows.get_mut(*kind);
+            window.anchor = placement.anchor;
+            window.offset = [placement.offset.x, placement.offset.y];
+            window.size = [rect.width(), rect.height()];
+        }
+    }
+
+    /// Same as apply, but into the config file, the file watcher then moves the panels into their snapped spots
+    pub fn save(&self, monitor: Rect, path: &Path) -> Result<()> {
+        // Just the file, command line overrides shouldn't end up in it
+        let mut config = config::read(path)?;
+        self.apply(monitor, &mut config.windows);
+        config::save(path, &config)
+    }
+}
@@ -0,0 +1,56 @@
+//! League of Legends Live Client API, the part of the overlay other tools can use too.
+//!
+//! [`LoLLiveClient`] has a typed async method per endpoint of the API League serves on
+//! `https://127.0.0.1:2999/liveclientdata` while a game runs, [`GameUpdates`] polls them all and
+//! hands out a [`GameUpdate`] for every change, and [`data`] has the models they deserialize into.
+//! [`dragontail`] adds the static data (names, descriptions, icons) from an extracted Data Dragon bundle.
+//!
+//! ```no_run
+//! # async fn run() -> anyhow::Result<()> {
+//! use league_overlay::{FetcherConfig, GameUpdate, GameUpdates};
+//!
+//! let mut updates = GameUpdates::spawn(FetcherConfig::default())?;
+//! while let Some(update) = updates.next().await {
+//!     match update {
+//!         // The whole snapshot once a game is found, then only the part that changed
+//!         GameUpdate::Info(info) => println!("level {}", info.active_player.level),
+//!         GameUpdate::ActivePlayer(active_player) => println!("level {}", active_player.level),
+//!         GameUpdate::GameData(game_data) => println!("{:.0}s in", game_data.game_time),
+//!         _ => {}
+//!     }
+//! }
+//! # Ok(())
+//! # }
+//! ```
+
+#![warn(missing_docs)]
+
+/// Where we are with the League client, from not running to the stats screen
+pub mod connection;
+/// Models the Live Client's JSON is parsed into
+pub mod data;
+/// Static data (names, descriptions, icons) from an extracted Data Dragon bundle
+pub mod dragontail;
+/// Which events have already been handed out
+pub mod event_cursor;
+/// The polling loop behind [`GameUpdates`]
+pub mod fetcher;
+/// A whole game snapshot and the updates sent about it
+pub mod game_info;
+/// The Live Client API, one typed method per endpoint
+pub mod live_client;
+/// A stand-in Live Client API serving fixtures or scripted games
+pub mod mock;
+/// How often each endpoint is polled
+pub mod polling;
+/// Writes what the Live Client says to a file, for replaying later
+pub mod recorder;
+/// Lenient parsing that reports payload changes instead of failing
+pub mod schema;
+/// Which certificates are trusted on the Live Client port
+pub mod tls;
+
+pub use fetcher::{FetcherConfig, GameUpdates};
+pub use game_info::{GameInfo, GameUpdate};
+pub use live_client::{LiveClientConfig, LiveClientError, LoLLiveClient};
+pub use schema::SchemaReport;
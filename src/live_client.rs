@@ -1,7 +1,20 @@
+use crate::GameInfo;
+use crate::data::events::{Event, EventsWrapper};
+use crate::data::items::Item;
+use crate::data::players::{Abilities, ActivePlayer, Player, Score};
+use crate::data::runes::{FullRunes, Rune};
+use crate::data::spells::SummonerSpells;
 use crate::game_info::GameData;
+use crate::schema::{self, SchemaReport};
+use crate::tls::{self, CertTrust};
 use anyhow::Result;
 use reqwest::{Client, ClientBuilder};
-use serde::Deserialize;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::error::Error as _;
+use std::path::PathBuf;
+use std::sync::Mutex;
 use std::time::Duration;
 /*
 This code is for the Leage Live Game API. This is in game stats aside from the normal API.
@@ -9,88 +22,300 @@ This will be combined iwth dragontail data to provide ability information as wel
 Item information wont be implemented because you can look up items in game (in due timeeee)
 */
 
+/// Everything that can go wrong talking to the Live Client, split up so the fetcher
+/// can tell "League isn't running" apart from "the payload changed"
+#[derive(Debug, thiserror::Error)]
+pub enum LiveClientError {
+    /// Nothing is listening on the port, League isn't running
+    #[error("League client is not running (connection refused)")]
+    ConnectionRefused,
+    /// No answer within 5 seconds
+    #[error("{endpoint} timed out")]
+    Timeout {
+        /// The path asked for, "/gamestats"
+        endpoint: String,
+    },
+    /// The certificate didn't check out (see tls.rs), or the handshake failed
+    #[error("TLS error talking to the League client: {message}")]
+    Tls {
+        /// What rustls said
+        message: String,
+    },
+    /// Anything but a 2xx, the API answers 404 while the game loads
+    #[error("{endpoint} returned HTTP {status}")]
+    HttpStatus {
+        /// The path asked for, "/gamestats"
+        endpoint: String,
+        /// The HTTP status code
+        status: u16,
+    },
+    /// The response isn't JSON, or doesn't fit the model even leniently
+    #[error("Failed to parse {endpoint} at {path}: {message}")]
+    Deserialize {
+        /// The path asked for, or where a payload we already had came from
+        endpoint: String,
+        /// Where in the JSON it went wrong, "." for the whole thing
+        path: String,
+        /// What serde said
+        message: String,
+    },
+    /// Any other failure reqwest reports
+    #[error("Request to {endpoint} failed: {source}")]
+    Request {
+        /// The path asked for, "/gamestats"
+        endpoint: String,
+        /// What reqwest said
+        source: reqwest::Error,
+    },
+}
+
+impl LiveClientError {
+    fn from_reqwest(endpoint: &str, error: reqwest::Error) -> Self {
+        if error.is_timeout() {
+            return LiveClientError::Timeout {
+                endpoint: endpoint.to_string(),
+            };
+        }
+
+        // reqwest doesn't say why a connect failed, so look for the io/tls error underneath
+        let mut source = error.source();
+        while let Some(cause) = source {
+            if let Some(tls) = cause.downcast_ref::<rustls::Error>() {
+                return LiveClientError::Tls {
+                    message: tls.to_string(),
+                };
+            }
+            if let Some(io) = cause.downcast_ref::<std::io::Error>() {
+                match io.kind() {
+                    std::io::ErrorKind::ConnectionRefused => {
+                        return LiveClientError::ConnectionRefused;
+                    }
+                    // rustls hands its errors back as InvalidData io errors
+                    std::io::ErrorKind::InvalidData => {
+                        return LiveClientError::Tls {
+                            message: io.get_ref().map_or(io.to_string(), |e| e.to_string()),
+                        };
+                    }
+                    _ => {}
+                }
+                // io::Error::source() skips over the error it wraps, so step into it ourselves
+                if let Some(inner) = io.get_ref() {
+                    source = Some(inner);
+                    continue;
+                }
+            }
+            source = cause.source();
+        }
+
+        LiveClientError::Request {
+            endpoint: endpoint.to_string(),
+            source: error,
+        }
+    }
+}
+
+/// Where to find the Live Client and which certificate to trust, the `[live_client]` section of the config
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct LiveClientConfig {
+    /// Base url of the API, endpoints go on the end of it
+    pub url: String,
+    /// Root certificate to pin instead of Riot's (see tls.rs)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ca_cert: Option<PathBuf>,
+    /// Accept any certificate (see tls.rs)
+    pub insecure: bool,
+}
+
+impl Default for LiveClientConfig {
+    fn default() -> Self {
+        Self {
+            url: "https://127.0.0.1:2999/liveclientdata".to_string(),
+            ca_cert: None,
+            insecure: false,
+        }
+    }
+}
+
+impl LiveClientConfig {
+    /// What ca_cert and insecure add up to
+    pub fn cert_trust(&self) -> CertTrust {
+        match (&self.ca_cert, self.insecure) {
+            (_, true) => CertTrust::Insecure,
+            (Some(path), false) => CertTrust::CaFile(path.clone()),
+            (None, false) => CertTrust::Riot,
+        }
+    }
+
+    /// Sets ca_cert and insecure to match `trust`
+    pub fn set_cert_trust(&mut self, trust: &CertTrust) {
+        (self.ca_cert, self.insecure) = match trust {
+            CertTrust::Riot => (None, false),
+            CertTrust::CaFile(path) => (Some(path.clone()), false),
+            CertTrust::Insecure => (None, true),
+        };
+    }
+}
+
+/// Talks to the Live Client API of a running game, one typed method per endpoint
 pub struct LoLLiveClient {
     client: Client,
     base_url: String,
+    //Fields that didn't match the models since this client was made
+    schema: Mutex<SchemaReport>,
 }
 
 impl LoLLiveClient {
-    pub fn new() -> Result<Self> {
-        //Create a client that accepts invalid certificates cuz it error'd (local???)
-        let client = ClientBuilder::new()
-            .danger_accept_invalid_certs(true)
-            .timeout(Duration::from_secs(5))
-            .build()?;
+    /// A client for `config`, fails if the certificate to pin can't be read
+    pub fn new(config: &LiveClientConfig) -> Result<Self> {
+        let builder = ClientBuilder::new().timeout(Duration::from_secs(5));
+        // Only the pinned root is trusted, unless verification was turned off (see tls.rs)
+        let client = match tls::client_config(&config.cert_trust())? {
+            Some(config) => builder.use_preconfigured_tls(config),
+            None => builder.danger_accept_invalid_certs(true),
+        }
+        .build()?;
 
-        let base_url = "https://127.0.0.1:2999/liveclientdata".to_string();
+        let base_url = config.url.trim_end_matches('/').to_string();
 
-        Ok(LoLLiveClient { client, base_url })
+        Ok(LoLLiveClient {
+            client,
+            base_url,
+            schema: Mutex::default(),
+        })
     }
 
-    async fn make_request<T>(&self, endpoint: &str) -> Result<T>
-    where
-        T: for<'de> Deserialize<'de>,
-    {
+    async fn make_request(
+        &self,
+        endpoint: &str,
+        query: &[(&str, &str)],
+    ) -> Result<Value, LiveClientError> {
         let url = format!("{}{}", self.base_url, endpoint);
-        let response = self.client.get(&url).send().await?;
+        let response = self
+            .client
+            .get(&url)
+            .query(query)
+            .send()
+            .await
+            .map_err(|e| LiveClientError::from_reqwest(endpoint, e))?;
         if !response.status().is_success() {
-            return Err(anyhow::anyhow!("HTTP error: {}", response.status()));
+            return Err(LiveClientError::HttpStatus {
+                endpoint: endpoint.to_string(),
+                status: response.status().as_u16(),
+            });
         }
 
-        //Get raw text first for better error reporting
-        let text = response.text().await?;
-
-        // Try to parse with detailed error info
-        match serde_json::from_str::<T>(&text) {
-            Ok(data) => Ok(data),
-            Err(e) => {
-                println!("=== PARSING ERROR ===");
-                println!("Endpoint: {}", endpoint);
-                println!("Error: {}", e);
-                println!("Error location: line {}, column {}", e.line(), e.column());
-
-                // Show the problematic area of JSON
-                let lines: Vec<&str> = text.lines().collect();
-                let error_line = e.line().saturating_sub(1);
-                let start = error_line.saturating_sub(2);
-                let end = (error_line + 3).min(lines.len());
-
-                println!("Context around error:");
-                for (i, line) in lines[start..end].iter().enumerate() {
-                    let line_num = start + i + 1;
-                    let marker = if line_num == e.line() { ">>> " } else { "    " };
-                    println!("{}{}: {}", marker, line_num, line);
-                }
-                println!("=== END PARSING ERROR ===");
+        let text = response
+            .text()
+            .await
+            .map_err(|e| LiveClientError::from_reqwest(endpoint, e))?;
+        serde_json::from_str(&text).map_err(|e| LiveClientError::Deserialize {
+            endpoint: endpoint.to_string(),
+            path: ".".to_string(),
+            message: e.to_string(),
+        })
+    }
 
-                Err(anyhow::anyhow!("JSON parsing failed: {}", e))
-            }
-        }
+    async fn get<T>(&self, endpoint: &str, query: &[(&str, &str)]) -> Result<T, LiveClientError>
+    where
+        T: DeserializeOwned + Serialize,
+    {
+        let value = self.make_request(endpoint, query).await?;
+        self.parse(endpoint, value)
+    }
+
+    /// Parses a payload we already have (like a polled snapshot) as leniently as the requests are,
+    /// fields that don't fit end up in schema_report
+    pub fn parse<T>(&self, endpoint: &str, value: Value) -> Result<T, LiveClientError>
+    where
+        T: DeserializeOwned + Serialize,
+    {
+        let mut report = self.schema.lock().unwrap_or_else(|e| e.into_inner());
+        schema::parse_lenient(endpoint, value, &mut report)
+    }
+
+    /// Every field that didn't match the models so far
+    pub fn schema_report(&self) -> SchemaReport {
+        self.schema
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .clone()
+    }
+
+    /// Any endpoint as plain json, e.g. "/gamestats"
+    pub async fn get_json(&self, endpoint: &str) -> Result<Value, LiveClientError> {
+        self.make_request(endpoint, &[]).await
+    }
+
+    /// Everything at once: active player, player list, events and game stats
+    pub async fn get_all_game_data(&self) -> Result<GameInfo, LiveClientError> {
+        self.get("/allgamedata", &[]).await
+    }
+
+    /// Game time and mode, the cheapest way to find out whether a game is running
+    pub async fn get_game_stats(&self) -> Result<GameData, LiveClientError> {
+        self.get("/gamestats", &[]).await
+    }
+
+    /// The whole event log so far, oldest first
+    pub async fn get_events(&self) -> Result<Vec<Event>, LiveClientError> {
+        let wrapper: EventsWrapper = self.get("/eventdata", &[]).await?;
+        Ok(wrapper.events)
+    }
+
+    /// The end of the event log, from the event with this id on (0 for all of it)
+    pub async fn get_events_since(&self, event_id: u32) -> Result<Vec<Event>, LiveClientError> {
+        let event_id = event_id.to_string();
+        let wrapper: EventsWrapper = self.get("/eventdata", &[("eventID", &event_id)]).await?;
+        Ok(wrapper.events)
+    }
+
+    /// The player this client belongs to, fails for spectators
+    pub async fn get_active_player(&self) -> Result<ActivePlayer, LiveClientError> {
+        self.get("/activeplayer", &[]).await
+    }
+
+    /// The active player's "name#tag"
+    pub async fn get_active_player_name(&self) -> Result<String, LiveClientError> {
+        self.get("/activeplayername", &[]).await
+    }
+
+    /// The active player's passive and Q/W/E/R with their levels
+    pub async fn get_active_player_abilities(&self) -> Result<Abilities, LiveClientError> {
+        self.get("/activeplayerabilities", &[]).await
     }
 
-    pub async fn is_game_active(&self) -> bool {
-        self.make_request::<GameData>("/gamestats").await.is_ok()
+    /// The active player's whole rune page, shards included
+    pub async fn get_active_player_runes(&self) -> Result<FullRunes, LiveClientError> {
+        self.get("/activeplayerrunes", &[]).await
     }
 
-    pub async fn get_all_game_data(&self) -> Result<serde_json::Value> {
-        self.make_request("/allgamedata").await
+    /// Everyone in the game, blue side first
+    pub async fn get_all_players(&self) -> Result<Vec<Player>, LiveClientError> {
+        self.get("/playerlist", &[]).await
     }
 
-    #[allow(dead_code)]
-    pub async fn get_events(&self) -> Result<serde_json::Value> {
-        self.make_request("/eventdata").await
+    /// KDA, CS and ward score for one player, by their "name#tag"
+    pub async fn get_player_scores(&self, riot_id: &str) -> Result<Score, LiveClientError> {
+        self.get("/playerscores", &[("riotId", riot_id)]).await
     }
 
-    #[allow(dead_code)]
-    pub async fn get_player_scores(&self) -> Result<serde_json::Value> {
-        self.make_request("/playerscores").await
+    /// Both summoner spells of one player
+    pub async fn get_player_summoner_spells(
+        &self,
+        riot_id: &str,
+    ) -> Result<SummonerSpells, LiveClientError> {
+        self.get("/playersummonerspells", &[("riotId", riot_id)])
+            .await
     }
 
-    pub async fn get_active_player(&self) -> Result<serde_json::Value> {
-        self.make_request("/activeplayer").await
+    /// Keystone and both trees of one player, the rest of the page is only there for the active player
+    pub async fn get_player_main_runes(&self, riot_id: &str) -> Result<Rune, LiveClientError> {
+        self.get("/playermainrunes", &[("riotId", riot_id)]).await
     }
 
-    pub async fn get_all_players(&self) -> Result<serde_json::Value> {
-        self.make_request("/playerlist").await
+    /// What one player is carrying, in no particular order (see Item::slot)
+    pub async fn get_player_items(&self, riot_id: &str) -> Result<Vec<Item>, LiveClientError> {
+        self.get("/playeritems", &[("riotId", riot_id)]).await
     }
 }
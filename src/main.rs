@@ -1,64 +1,111 @@
 #![windows_subsystem = "windows"]
 use anyhow::Result;
 use eframe::egui;
-use tokio::sync::mpsc;
-mod data;
-mod fetcher;
-mod game_info;
+use tokio::sync::{mpsc, watch};
+mod cli;
+mod config;
+mod expr;
 mod hotkey;
-mod live_client;
+mod icons;
+mod inventory;
+mod layout;
 mod overlay;
+mod panels;
+mod placement;
+mod replay;
+mod settings;
+mod source;
+mod stat_rows;
+mod visibility;
 
 #[cfg(target_os = "windows")]
 mod windows;
 
-use fetcher::game_data_fetcher;
-use game_info::GameInfo;
-use hotkey::setup_global_hotkey;
+use cli::Args;
+use config::{Config, LoadedConfig};
+use hotkey::{HotkeyAction, Hotkeys};
+use league_overlay::GameUpdate;
 use overlay::OverlayApp;
 
 #[cfg(target_os = "windows")]
 use windows::apply_window_styling;
 
-#[cfg(feature = "res_1920")]
-const SCREEN_WIDTH: f32 = 1920.0;
-#[cfg(feature = "res_1920")]
-const SCREEN_HEIGHT: f32 = 1080.0;
-
-#[cfg(feature = "res_2560")]
-const SCREEN_WIDTH: f32 = 2560.0;
-#[cfg(feature = "res_2560")]
-const SCREEN_HEIGHT: f32 = 1440.0;
-
 // Embed the icon PNG file at compile time
 const ICON_PNG_BYTES: &[u8] = include_bytes!("assets/icon.png");
 
+// The library reports through the log crate, printed here like the rest of the overlay's output
+struct StdoutLogger;
+
+impl log::Log for StdoutLogger {
+    fn enabled(&self, metadata: &log::Metadata) -> bool {
+        metadata.level() <= log::Level::Info && metadata.target().starts_with("league_overlay")
+    }
+
+    fn log(&self, record: &log::Record) {
+        if self.enabled(record.metadata()) {
+            println!("{}", record.args());
+        }
+    }
+
+    fn flush(&self) {}
+}
+
 #[tokio::main]
 async fn main() -> Result<()> {
+    log::set_logger(&StdoutLogger)?;
+    log::set_max_level(log::LevelFilter::Info);
     println!("Starting League of Legends Overlay...");
+    let args = Args::parse()?;
 
-    // Set up global hotkey
-    let (hotkey_sender, hotkey_receiver) = mpsc::unbounded_channel::<()>();
-    let _hotkey_manager = setup_global_hotkey(hotkey_sender)?;
+    // Load the config file and keep an eye on it for edits
+    let config_path = args.config.clone().or_else(config::default_path);
+    let config = match &config_path {
+        Some(path) => {
+            config::create_default(path)?;
+            config::load(path, &args)?
+        }
+        None => {
+            println!("No config directory found, using the default settings");
+            let mut config = Config::default();
+            args.apply_overrides(&mut config)?;
+            config
+        }
+    };
+    let window_size = config.windows.stats.size();
+    let hotkey_bindings = config.hotkey_bindings();
+    let (config_sender, config_receiver) = watch::channel(LoadedConfig {
+        config,
+        error: None,
+    });
+    let _config_watcher = config_path.clone().and_then(|path| {
+        config::watch(path, args.clone(), config_sender)
+            .inspect_err(|e| println!("Config changes won't be picked up: {:#}", e))
+            .ok()
+    });
+
+    let source = source::from_args(&args, config_receiver.clone())?;
+
+    // Set up global hotkeys
+    let (hotkey_sender, hotkey_receiver) = mpsc::unbounded_channel::<HotkeyAction>();
+    let replay_controls = source.replay_controls();
+    let hotkeys = Hotkeys::new(&hotkey_bindings, replay_controls.is_some(), hotkey_sender)?;
 
     // Create mpsc channel for game data
-    let (sender, receiver) = mpsc::unbounded_channel::<GameInfo>();
+    let (sender, receiver) = mpsc::unbounded_channel::<GameUpdate>();
 
-    // Spawn background task to fetch game data
+    // Spawn background task to fetch game data from whichever source was picked
+    println!("Game data source: {}", source.name());
     let data_sender = sender.clone();
     tokio::spawn(async move {
-        if let Err(e) = game_data_fetcher(data_sender).await {
-            println!("Game data fetcher error: {}", e);
+        if let Err(e) = source.run(data_sender).await {
+            println!("Game data source error: {}", e);
         }
     });
 
-    // Set up the GUI
-    let vertical_scale: f32 = 1.2;
-    let horizontal_scale: f32 = 1.3;
+    // Set up the GUI, the overlay moves itself into place once it knows the monitor size
     let options = eframe::NativeOptions {
         viewport: egui::ViewportBuilder::default()
-            .with_inner_size([355.0 * horizontal_scale, 195.0 * vertical_scale])
-            .with_position(egui::pos2(SCREEN_WIDTH - 360.0 * horizontal_scale, 55.0))
+            .with_inner_size(window_size)
             .with_decorations(false)
             .with_icon({
                 let mut decoder = png::Decoder::new(ICON_PNG_BYTES);
@@ -78,7 +125,14 @@ async fn main() -> Result<()> {
         ..Default::default()
     };
 
-    let app = OverlayApp::new(receiver, hotkey_receiver);
+    let app = OverlayApp::new(
+        receiver,
+        hotkeys,
+        hotkey_receiver,
+        replay_controls,
+        config_receiver,
+        config_path,
+    );
 
     if let Err(e) = eframe::run_native(
         "League Overlay",
@@ -86,7 +140,7 @@ async fn main() -> Result<()> {
         Box::new(move |cc| {
             #[cfg(target_os = "windows")]
             apply_window_styling(cc);
-
+            app.wake_on_hotkeys(&cc.egui_ctx);
             Ok(Box::new(app))
         }),
     ) {
@@ -0,0 +1,78 @@
+use crate::mock::server::{GameSource, part, player_part};
+use anyhow::{Context, Result};
+use serde_json::Value;
+use std::path::{Path, PathBuf};
+
+/*
+A fixture directory needs at least allgamedata.json.
+Every other endpoint is cut out of it unless there is a file with the endpoint's name
+(activeplayer.json, playerlist.json, eventdata.json, gamestats.json, playerscores.json)
+to override it. The narrower ones (activeplayerrunes, playeritems, ...) are cut out of those in turn. Files are read on every request so they can be edited while the server runs.
+*/
+
+/// A fixture directory, see the top of the file
+pub struct Fixtures {
+    dir: PathBuf,
+}
+
+impl Fixtures {
+    /// Fails if there's no readable allgamedata.json in `dir`
+    pub fn open(dir: &Path) -> Result<Self> {
+        let fixtures = Fixtures {
+            dir: dir.to_path_buf(),
+        };
+        // Fail on startup instead of on the first request
+        fixtures.all_game_data()?;
+        Ok(fixtures)
+    }
+
+    fn read_or(&self, name: &str, key: &str) -> Result<Value> {
+        match self.read(name)? {
+            Some(value) => Ok(value),
+            None => part(self.all_game_data()?, key),
+        }
+    }
+
+    fn read(&self, name: &str) -> Result<Option<Value>> {
+        let path = self.dir.join(format!("{}.json", name));
+        if !path.exists() {
+            return Ok(None);
+        }
+        let text = std::fs::read_to_string(&path)
+            .with_context(|| format!("Failed to read {}", path.display()))?;
+        let value = serde_json::from_str(&text)
+            .with_context(|| format!("{} is not valid json", path.display()))?;
+        Ok(Some(value))
+    }
+}
+
+impl GameSource for Fixtures {
+    fn all_game_data(&self) -> Result<Value> {
+        self.read("allgamedata")?
+            .with_context(|| format!("{} has no allgamedata.json", self.dir.display()))
+    }
+
+    fn active_player(&self) -> Result<Value> {
+        self.read_or("activeplayer", "activePlayer")
+    }
+
+    fn player_list(&self) -> Result<Value> {
+        self.read_or("playerlist", "allPlayers")
+    }
+
+    fn event_data(&self) -> Result<Value> {
+        self.read_or("eventdata", "events")
+    }
+
+    fn game_stats(&self) -> Result<Value> {
+        self.read_or("gamestats", "gameData")
+    }
+
+    /// playerscores.json (if there is one) is an object of riotId -> scores
+    fn player_scores(&self, riot_id: &str) -> Result<Option<Value>> {
+        match self.read("playerscores")? {
+            Some(scores) => Ok(scores.get(riot_id).cloned()),
+            None => player_part(self.player_list()?, riot_id, "scores"),
+        }
+    }
+}
@@ -0,0 +1,12 @@
+/*
+Stand-in for the Live Client API, for running the overlay (or any other tool) without League.
+fixtures serves json files as they are, synthetic plays a scripted game, and server puts either
+behind the same routes as the real client. The mock_live_client binary serves them over https.
+*/
+
+/// A directory of json files to serve
+pub mod fixtures;
+/// The Live Client's routes over any GameSource
+pub mod server;
+/// Games played out from a toml script
+pub mod synthetic;
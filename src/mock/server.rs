@@ -0,0 +1,232 @@
+use crate::mock::synthetic::ScriptedGame;
+use anyhow::{Context, Result, bail};
+use axum::extract::{Query, State};
+use axum::http::StatusCode;
+use axum::response::{IntoResponse, Response};
+use axum::routing::get;
+use axum::{Json, Router};
+use serde::Deserialize;
+use serde_json::{Value, json};
+use std::sync::Arc;
+
+/// Where the payloads come from (fixture files or a scripted game).
+/// Everything is cut out of allgamedata unless a source has something better.
+pub trait GameSource: Send + Sync + 'static {
+    /// /allgamedata
+    fn all_game_data(&self) -> Result<Value>;
+
+    /// /activeplayer, and the activeplayer* endpoints cut out of it
+    fn active_player(&self) -> Result<Value> {
+        part(self.all_game_data()?, "activePlayer")
+    }
+
+    /// /playerlist
+    fn player_list(&self) -> Result<Value> {
+        part(self.all_game_data()?, "allPlayers")
+    }
+
+    /// /eventdata, every event (the server filters by eventID)
+    fn event_data(&self) -> Result<Value> {
+        part(self.all_game_data()?, "events")
+    }
+
+    /// /gamestats
+    fn game_stats(&self) -> Result<Value> {
+        part(self.all_game_data()?, "gameData")
+    }
+
+    /// /playerscores for one player, None if there's no such player
+    fn player_scores(&self, riot_id: &str) -> Result<Option<Value>> {
+        player_part(self.player_list()?, riot_id, "scores")
+    }
+}
+
+impl GameSource for ScriptedGame {
+    fn all_game_data(&self) -> Result<Value> {
+        Ok(self.snapshot_now())
+    }
+}
+
+/// One key of allgamedata, e.g. "activePlayer"
+pub fn part(mut all_game_data: Value, key: &str) -> Result<Value> {
+    all_game_data
+        .get_mut(key)
+        .map(Value::take)
+        .with_context(|| format!("allgamedata has no {}", key))
+}
+
+/// One key of one player in the player list, None if there's no such player
+pub fn player_part(players: Value, riot_id: &str, key: &str) -> Result<Option<Value>> {
+    let Value::Array(players) = players else {
+        bail!("playerlist is not an array");
+    };
+    Ok(players
+        .into_iter()
+        .find(|p| p.get("riotId").and_then(Value::as_str) == Some(riot_id))
+        .and_then(|mut p| p.get_mut(key).map(Value::take)))
+}
+
+/// Every /liveclientdata route, answering from `source`
+pub fn router(source: Arc<dyn GameSource>) -> Router {
+    Router::new()
+        .route("/liveclientdata/allgamedata", get(all_game_data))
+        .route("/liveclientdata/activeplayer", get(active_player))
+        .route("/liveclientdata/activeplayername", get(active_player_name))
+        .route(
+            "/liveclientdata/activeplayerabilities",
+            get(active_player_abilities),
+        )
+        .route(
+            "/liveclientdata/activeplayerrunes",
+            get(active_player_runes),
+        )
+        .route("/liveclientdata/playerlist", get(player_list))
+        .route("/liveclientdata/eventdata", get(event_data))
+        .route("/liveclientdata/playerscores", get(player_scores))
+        .route(
+            "/liveclientdata/playersummonerspells",
+            get(player_summoner_spells),
+        )
+        .route("/liveclientdata/playermainrunes", get(player_main_runes))
+        .route("/liveclientdata/playeritems", get(player_items))
+        .route("/liveclientdata/gamestats", get(game_stats))
+        .fallback(|| async {
+            riot_error(
+                StatusCode::NOT_FOUND,
+                "RESOURCE_NOT_FOUND",
+                "Unknown endpoint",
+            )
+        })
+        .with_state(source)
+}
+
+type AppState = State<Arc<dyn GameSource>>;
+
+async fn all_game_data(State(source): AppState) -> Response {
+    respond(source.all_game_data())
+}
+
+async fn active_player(State(source): AppState) -> Response {
+    respond(source.active_player())
+}
+
+async fn active_player_name(State(source): AppState) -> Response {
+    respond(source.active_player().and_then(|p| part(p, "riotId")))
+}
+
+async fn active_player_abilities(State(source): AppState) -> Response {
+    respond(source.active_player().and_then(|p| part(p, "abilities")))
+}
+
+async fn active_player_runes(State(source): AppState) -> Response {
+    respond(source.active_player().and_then(|p| part(p, "fullRunes")))
+}
+
+async fn player_list(State(source): AppState) -> Response {
+    respond(source.player_list())
+}
+
+#[derive(Deserialize)]
+struct EventQuery {
+    #[serde(rename = "eventID")]
+    event_id: Option<u64>,
+}
+
+// ?eventID= leaves out the events before that one, like the real client
+async fn event_data(State(source): AppState, Query(query): Query<EventQuery>) -> Response {
+    respond(source.event_data().map(|mut events| {
+        if let Some(first) = query.event_id
+            && let Some(Value::Array(list)) = events.get_mut("Events")
+        {
+            list.retain(|e| e.get("EventID").and_then(Value::as_u64) >= Some(first));
+        }
+        events
+    }))
+}
+
+async fn game_stats(State(source): AppState) -> Response {
+    respond(source.game_stats())
+}
+
+#[derive(Deserialize)]
+struct PlayerQuery {
+    #[serde(rename = "riotId")]
+    riot_id: Option<String>,
+}
+
+async fn player_scores(State(source): AppState, Query(query): Query<PlayerQuery>) -> Response {
+    respond_for_player(query, |riot_id| source.player_scores(riot_id))
+}
+
+async fn player_summoner_spells(
+    State(source): AppState,
+    Query(query): Query<PlayerQuery>,
+) -> Response {
+    respond_for_player(query, |riot_id| {
+        player_part(source.player_list()?, riot_id, "summonerSpells")
+    })
+}
+
+async fn player_main_runes(State(source): AppState, Query(query): Query<PlayerQuery>) -> Response {
+    respond_for_player(query, |riot_id| {
+        player_part(source.player_list()?, riot_id, "runes")
+    })
+}
+
+async fn player_items(State(source): AppState, Query(query): Query<PlayerQuery>) -> Response {
+    respond_for_player(query, |riot_id| {
+        player_part(source.player_list()?, riot_id, "items")
+    })
+}
+
+// The ?riotId= endpoints, which 404 like the real ones for players that aren't in the game
+fn respond_for_player(
+    query: PlayerQuery,
+    lookup: impl FnOnce(&str) -> Result<Option<Value>>,
+) -> Response {
+    let Some(riot_id) = query.riot_id else {
+        return riot_error(
+            StatusCode::BAD_REQUEST,
+            "INVALID_PARAMETER",
+            "riotId is required",
+        );
+    };
+    match lookup(&riot_id) {
+        Ok(Some(value)) => Json(value).into_response(),
+        Ok(None) => riot_error(
+            StatusCode::NOT_FOUND,
+            "RESOURCE_NOT_FOUND",
+            &format!("No player with riotId {}", riot_id),
+        ),
+        Err(e) => server_error(e),
+    }
+}
+
+fn respond(result: Result<Value>) -> Response {
+    match result {
+        Ok(value) => Json(value).into_response(),
+        Err(e) => server_error(e),
+    }
+}
+
+fn server_error(e: anyhow::Error) -> Response {
+    log::error!("Source error: {:#}", e);
+    riot_error(
+        StatusCode::INTERNAL_SERVER_ERROR,
+        "SOURCE_ERROR",
+        &format!("{:#}", e),
+    )
+}
+
+//Same shape as the errors the real client sends back
+fn riot_error(status: StatusCode, code: &str, message: &str) -> Response {
+    (
+        status,
+        Json(json!({
+            "errorCode": code,
+            "httpStatus": status.as_u16(),
+            "message": message,
+        })),
+    )
+        .into_response()
+}
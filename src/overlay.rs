@@ -1,61 +1,459 @@
-use crate::GameInfo;
+use crate::config::{Config, ConfigReceiver, RowConfig};
+use crate::expr::Vars;
+use crate::hotkey::{HotkeyAction, Hotkeys};
+use crate::icons::{Icon, IconCache};
+use crate::inventory;
+use crate::layout::LayoutEditor;
+use crate::panels::{self, ObjectiveTimers, PanelKind, clock};
+use crate::replay::{self, ReplayCommand};
+use crate::settings::{self, SettingsMenu};
+use crate::stat_rows::{self, CompiledPanel, CompiledRow};
+use crate::visibility::{self, GameState, Visibility};
 use eframe::egui;
-use egui::{Color32, Frame, Layout, RichText, TextureHandle};
-use std::collections::HashMap;
+use egui::{Color32, Frame, Layout, RichText};
+use league_overlay::connection::{ConnectionState, ConnectionStatus};
+use league_overlay::data::events::{Event, EventKind};
+use league_overlay::dragontail::Dragontail;
+use league_overlay::{GameInfo, GameUpdate};
+use std::collections::VecDeque;
+use std::path::PathBuf;
 use std::sync::{Arc, Mutex};
 use tokio::sync::mpsc;
 
-#[cfg(feature = "res_1920")]
-const SCREEN_WIDTH: f32 = 1920.0;
-
-#[cfg(feature = "res_2560")]
-const SCREEN_WIDTH: f32 = 2560.0;
+//How many of the latest events we hang on to
+const MAX_RECENT_EVENTS: usize = 50;
 
 pub struct OverlayApp {
     game_info: Arc<Mutex<Option<GameInfo>>>,
-    game_data_receiver: mpsc::UnboundedReceiver<GameInfo>,
-    icons: HashMap<String, TextureHandle>,
+    game_data_receiver: mpsc::UnboundedReceiver<GameUpdate>,
+    recent_events: VecDeque<Event>,
+    //Only set by the live source, None means we don't know (or it doesn't apply)
+    connection: Option<ConnectionStatus>,
+    icons: IconCache,
     styles_initialized: bool,
-    visible: Arc<Mutex<bool>>,
-    hotkey_receiver: mpsc::UnboundedReceiver<()>,
+    visibility: Visibility,
+    hotkeys: Hotkeys,
+    hotkey_receiver: mpsc::UnboundedReceiver<HotkeyAction>,
+    //Where the replay hotkeys go, None unless a recording is playing
+    replay_controls: Option<mpsc::UnboundedSender<ReplayCommand>>,
     data_changed: bool,
+    config_receiver: ConfigReceiver,
+    config: Config,
+    //The config's stat rows ready to evaluate, None if they don't compile
+    panel: Option<CompiledPanel>,
+    //Where the settings menu saves to, None when there's no config file
+    config_path: Option<PathBuf>,
+    //Open settings menu, the overlay takes mouse input while it's up
+    settings: Option<SettingsMenu>,
+    //Takes mouse input without the menu (toggle_interactive hotkey)
+    interactive: bool,
+    //Edit layout mode, every panel is up and can be dragged around
+    layout: Option<LayoutEditor>,
+    //Panels switched on, starts as the ones the config has visible
+    shown: Vec<PanelKind>,
+    //Only this panel while cycle_panel is stepping through them
+    solo: Option<PanelKind>,
+    objectives: ObjectiveTimers,
+    //Game times marked with the mark_highlight hotkey
+    highlights: Vec<f64>,
+    //Shown under the panel until the egui time runs out
+    toast: Option<(String, f64)>,
+    //Why the last config edit (or hotkey rebind) didn't take
+    config_error: Option<String>,
+    //The monitor in points (at the origin, see placement.rs) and its scale factor, None until egui tells us
+    monitor: Option<(egui::Rect, f32)>,
+    //Where the main window was last put, it's only moved again when that changes
+    placed: Option<(egui::Pos2, egui::Vec2)>,
+    //Whether the main window was last drawn into, it's emptied instead of hidden (see fade_main_window)
+    main_window_shown: Option<bool>,
+    //Panels whose window has been made, they're kept (hidden) once they have been
+    panel_windows: Vec<PanelKind>,
+    //Static game data, None without a [dragontail] path or if it didn't load
+    dragontail: Option<Arc<Dragontail>>,
+}
+
+// What the stat rows are worked out from
+struct PlayerView {
+    riot_id: String,
+    vars: Vars,
+    is_dead: bool,
+    respawn_timer: f64,
 }
 
 impl OverlayApp {
     pub fn new(
-        game_data_receiver: mpsc::UnboundedReceiver<GameInfo>,
-        hotkey_receiver: mpsc::UnboundedReceiver<()>,
+        game_data_receiver: mpsc::UnboundedReceiver<GameUpdate>,
+        hotkeys: Hotkeys,
+        hotkey_receiver: mpsc::UnboundedReceiver<HotkeyAction>,
+        replay_controls: Option<mpsc::UnboundedSender<ReplayCommand>>,
+        mut config_receiver: ConfigReceiver,
+        config_path: Option<PathBuf>,
     ) -> Self {
-        Self {
+        let config = config_receiver.borrow_and_update().config.clone();
+        let panel = CompiledPanel::compile(&config.panel);
+        let config_error = panel.as_ref().err().map(|e| format!("{:#}", e));
+        let shown = PanelKind::ALL
+            .into_iter()
+            .filter(|kind| config.windows.get(*kind).visible)
+            .collect();
+        let mut app = Self {
             game_info: Arc::new(Mutex::new(None)),
             game_data_receiver,
-            icons: HashMap::new(),
+            recent_events: VecDeque::with_capacity(MAX_RECENT_EVENTS),
+            connection: None,
+            icons: IconCache::new(),
             styles_initialized: false,
-            visible: Arc::new(Mutex::new(true)),
+            visibility: Visibility::default(),
+            hotkeys,
             hotkey_receiver,
+            replay_controls,
             data_changed: true,
+            config_receiver,
+            config,
+            panel: panel.ok(),
+            config_path,
+            settings: None,
+            interactive: false,
+            layout: None,
+            shown,
+            solo: None,
+            objectives: ObjectiveTimers::default(),
+            highlights: Vec::new(),
+            toast: None,
+            config_error,
+            monitor: None,
+            placed: None,
+            main_window_shown: None,
+            panel_windows: Vec::new(),
+            dragontail: None,
+        };
+        app.load_dragontail();
+        app.update_icon_sources();
+        app
+    }
+
+    fn update_icon_sources(&mut self) {
+        self.icons
+            .set_sources(self.config.icons.path.clone(), self.dragontail.clone());
+    }
+
+    fn load_dragontail(&mut self) {
+        let config = &self.config.dragontail;
+        self.dragontail = None;
+        let Some(path) = &config.path else {
+            return;
+        };
+        match Dragontail::load(path, &config.locale, config.patch.as_deref()) {
+            Ok(dragontail) => {
+                println!(
+                    "Loaded dragontail {} ({})",
+                    dragontail.patch(),
+                    dragontail.locale()
+                );
+                self.dragontail = Some(Arc::new(dragontail));
+            }
+            Err(e) => {
+                println!("Not using dragontail: {}", e);
+                self.config_error = Some(format!("dragontail: {}", e));
+            }
+        }
+    }
+
+    /// Has hotkey presses repaint the overlay, so they're handled even while it's faded out
+    pub fn wake_on_hotkeys(&self, ctx: &egui::Context) {
+        self.hotkeys.wake(ctx);
+    }
+
+    fn is_shown(&self, kind: PanelKind) -> bool {
+        if self.layout.is_some() {
+            return true;
+        }
+        self.visibility.is_visible()
+            && match self.solo {
+                Some(solo) => solo == kind,
+                None => self.shown.contains(&kind),
+            }
+    }
+
+    // The main window holds the stats panel and the settings menu, which needs more room
+    fn window_size(&self) -> egui::Vec2 {
+        let size = self.config.windows.stats.size();
+        if self.settings.is_some() {
+            size.max(settings::MENU_SIZE)
+        } else {
+            size
+        }
+    }
+
+    // Where the main window should be, None until the monitor size is known
+    fn main_geometry(&self) -> Option<(egui::Pos2, egui::Vec2)> {
+        let (monitor, _) = self.monitor?;
+        let size = self.window_size();
+        let position = self
+            .config
+            .windows
+            .stats
+            .placement()
+            .position(monitor, size);
+        Some((position, size))
+    }
+
+    fn place_main_window(&mut self, ctx: &egui::Context) {
+        let geometry = self.main_geometry();
+        if let Some((position, size)) = geometry
+            && geometry != self.placed
+        {
+            ctx.send_viewport_cmd(egui::ViewportCommand::OuterPosition(position));
+            ctx.send_viewport_cmd(egui::ViewportCommand::InnerSize(size));
+            self.placed = geometry;
+        }
+    }
+
+    // The menu and the layout handles keep the main window up even with the stats panel hidden
+    fn main_window_wanted(&self) -> bool {
+        self.settings.is_some() || self.layout.is_some() || self.is_shown(PanelKind::Stats)
+    }
+
+    // Fades the main window in or out, it's only really hidden once it's faded all the way
+    fn fade_main_window(&mut self, ctx: &egui::Context) -> f32 {
+        let opacity = visibility::fade(
+            ctx,
+            egui::Id::new("main_window"),
+            self.main_window_wanted(),
+            &self.config.visibility,
+        );
+        // Never Visible(false): Windows stops repainting a hidden root window, and then nothing
+        // would be left to handle the hotkey that brings it back. Empty and click-through will do
+        let shown = opacity > 0.0;
+        if self.main_window_shown != Some(shown) {
+            self.main_window_shown = Some(shown);
+            self.update_passthrough(ctx);
         }
+        opacity
     }
 
-    fn get_or_load_icon(
-        &mut self,
-        _ctx: &egui::Context,
-        name: &str,
-        fallback_emoji: &str,
-    ) -> String {
-        // For now, return emoji fallback - you can extend this to load actual images
-        match name {
-            "attack_damage" => "⚔".to_string(),
-            "ability_power" => "💫".to_string(),
-            "armor" => "🛡".to_string(),
-            "magic_resist" => "🟣".to_string(),
-            "attack_speed" => "⚡".to_string(),
-            "health_regen" => "💉".to_string(),
-            "life_steal" => "❤".to_string(),
-            "gold" => "💰".to_string(),
-            _ => fallback_emoji.to_string(),
+    fn game_state(&self) -> GameState {
+        GameState {
+            connection: self.connection.as_ref().map(|status| status.state),
+            has_game: self
+                .game_info
+                .lock()
+                .is_ok_and(|game_info| game_info.is_some()),
+            game_ended: self
+                .recent_events
+                .iter()
+                .any(|event| matches!(event.kind, EventKind::GameEnd { .. })),
         }
     }
+
+    fn apply_styles(&self, ctx: &egui::Context) {
+        let panel = &self.config.panel;
+        let family = panel.font.family();
+        ctx.style_mut(|style| {
+            style.text_styles.insert(
+                egui::TextStyle::Body,
+                egui::FontId::new(panel.font_size, family.clone()),
+            );
+            style.text_styles.insert(
+                egui::TextStyle::Heading,
+                egui::FontId::new(panel.font_size + 5.0, family.clone()),
+            );
+        });
+    }
+
+    // Clicks go through to the game unless something on the overlay wants them
+    fn update_passthrough(&self, ctx: &egui::Context) {
+        let takes_clicks = self.main_window_shown != Some(false)
+            && (self.settings.is_some() || self.interactive || self.layout.is_some());
+        ctx.send_viewport_cmd(egui::ViewportCommand::MousePassthrough(!takes_clicks));
+    }
+
+    fn open_settings(&mut self, ctx: &egui::Context) {
+        self.finish_layout(ctx);
+        self.settings = Some(SettingsMenu::open(&self.config, self.config_path.clone()));
+        self.update_passthrough(ctx);
+        ctx.send_viewport_cmd(egui::ViewportCommand::Focus);
+    }
+
+    fn close_settings(&mut self, ctx: &egui::Context) {
+        self.settings = None;
+        self.update_passthrough(ctx);
+    }
+
+    fn start_layout(&mut self, ctx: &egui::Context) {
+        self.close_settings(ctx);
+        self.layout = Some(LayoutEditor::default());
+        self.update_passthrough(ctx);
+        let text = format!(
+            "Drag the panels into place, {} again to save",
+            self.config.hotkeys.edit_layout
+        );
+        self.show_toast(ctx, text);
+    }
+
+    // Keeps the panels where they were dropped, in the config file if there is one
+    fn finish_layout(&mut self, ctx: &egui::Context) {
+        let Some(layout) = self.layout.take() else {
+            return;
+        };
+        self.update_passthrough(ctx);
+        let Some((monitor, _)) = self.monitor else {
+            return;
+        };
+        let text = match &self.config_path {
+            Some(path) => match layout.save(monitor, path) {
+                Ok(()) => "Layout saved".to_string(),
+                Err(e) => {
+                    println!("Couldn't save the layout: {:#}", e);
+                    format!("Couldn't save the layout: {:#}", e)
+                }
+            },
+            None => {
+                layout.apply(monitor, &mut self.config.windows);
+                "Layout changed, there's no config file to save it to".to_string()
+            }
+        };
+        self.show_toast(ctx, text);
+    }
+
+    fn show_toast(&mut self, ctx: &egui::Context, text: String) {
+        let until = ctx.input(|i| i.time) + 3.0;
+        self.toast = Some((text, until));
+    }
+
+    fn handle_hotkey(&mut self, ctx: &egui::Context, action: HotkeyAction) {
+        let is_visible = self.visibility.is_visible();
+        match action {
+            HotkeyAction::ToggleOverlay => self.toggle_visible(ctx),
+            HotkeyAction::OpenSettings => {
+                if self.settings.is_some() {
+                    self.close_settings(ctx);
+                } else if is_visible {
+                    self.open_settings(ctx);
+                }
+            }
+            HotkeyAction::CyclePanel => {
+                // All of them, then each one alone, then back to all
+                self.solo = match self.solo {
+                    None => Some(PanelKind::ALL[0]),
+                    Some(kind) => PanelKind::ALL
+                        .into_iter()
+                        .skip_while(|other| *other != kind)
+                        .nth(1),
+                };
+                let text = match self.solo {
+                    Some(kind) => format!("Only {}", kind.title()),
+                    None => "All panels".to_string(),
+                };
+                self.show_toast(ctx, text);
+            }
+            HotkeyAction::TogglePanel(kind) => {
+                self.solo = None;
+                if let Some(i) = self.shown.iter().position(|shown| *shown == kind) {
+                    self.shown.remove(i);
+                } else {
+                    self.shown.push(kind);
+                }
+                println!("{} shown: {}", kind.title(), self.shown.contains(&kind));
+            }
+            HotkeyAction::ToggleInteractive => {
+                self.interactive = !self.interactive;
+                self.update_passthrough(ctx);
+                println!("Overlay takes clicks: {}", self.interactive);
+            }
+            HotkeyAction::EditLayout => {
+                if self.layout.is_some() {
+                    self.finish_layout(ctx);
+                } else if is_visible {
+                    self.start_layout(ctx);
+                }
+            }
+            HotkeyAction::MarkHighlight => {
+                let game_time = self
+                    .game_info
+                    .lock()
+                    .ok()
+                    .and_then(|game_info| game_info.as_ref().map(|g| g.game_data.game_time));
+                let text = match game_time {
+                    Some(time) => {
+                        self.highlights.push(time);
+                        println!("Highlight marked at {}", clock(time));
+                        format!("★ Highlight marked at {}", clock(time))
+                    }
+                    None => "No game to mark a highlight in".to_string(),
+                };
+                self.show_toast(ctx, text);
+            }
+            HotkeyAction::ReplayPause
+            | HotkeyAction::ReplaySlower
+            | HotkeyAction::ReplayFaster
+            | HotkeyAction::ReplayBack
+            | HotkeyAction::ReplayForward => {
+                let command = match action {
+                    HotkeyAction::ReplayPause => ReplayCommand::TogglePause,
+                    HotkeyAction::ReplaySlower => ReplayCommand::Slower,
+                    HotkeyAction::ReplayFaster => ReplayCommand::Faster,
+                    HotkeyAction::ReplayBack => ReplayCommand::SeekBy(-replay::SEEK_STEP),
+                    _ => ReplayCommand::SeekBy(replay::SEEK_STEP),
+                };
+                if let Some(controls) = &self.replay_controls
+                    && controls.send(command).is_ok()
+                {
+                    self.show_toast(ctx, action.description());
+                }
+            }
+        }
+    }
+
+    fn toggle_visible(&mut self, ctx: &egui::Context) {
+        // Hiding takes the menu with it, and ends the layout editing
+        if self.settings.is_some() {
+            self.settings = None;
+            self.update_passthrough(ctx);
+        }
+        self.finish_layout(ctx);
+        self.visibility.toggle();
+    }
+
+    // Picks up edits to the config file, window changes apply straight away
+    fn apply_config_changes(&mut self, ctx: &egui::Context) {
+        if !self.config_receiver.has_changed().unwrap_or(false) {
+            return;
+        }
+        let loaded = self.config_receiver.borrow_and_update().clone();
+        let old = std::mem::replace(&mut self.config, loaded.config);
+        self.config_error = loaded.error;
+
+        let bindings = self.config.hotkey_bindings();
+        if old.hotkey_bindings() != bindings
+            && let Err(e) = self.hotkeys.rebind(&bindings)
+        {
+            println!("{:#}", e);
+            self.config_error = Some(format!("{:#}", e));
+        }
+        // A panel switched on or off in the file wins over its hotkey
+        for kind in PanelKind::ALL {
+            let visible = self.config.windows.get(kind).visible;
+            if old.windows.get(kind).visible != visible {
+                self.shown.retain(|shown| *shown != kind);
+                if visible {
+                    self.shown.push(kind);
+                }
+            }
+        }
+        if old.panel != self.config.panel {
+            self.apply_styles(ctx);
+            match CompiledPanel::compile(&self.config.panel) {
+                Ok(panel) => self.panel = Some(panel),
+                Err(e) => self.config_error = Some(format!("{:#}", e)),
+            }
+        }
+        if old.dragontail != self.config.dragontail {
+            self.load_dragontail();
+        }
+        self.update_icon_sources();
+        self.data_changed = true;
+    }
 }
 
 impl eframe::App for OverlayApp {
@@ -66,58 +464,46 @@ impl eframe::App for OverlayApp {
             ctx.send_viewport_cmd(egui::ViewportCommand::Transparent(true));
             ctx.send_viewport_cmd(egui::ViewportCommand::Decorations(false));
 
-            ctx.style_mut(|style| {
-                style.text_styles.insert(
-                    egui::TextStyle::Body,
-                    egui::FontId::new(15.0, egui::FontFamily::Monospace),
-                );
-                style.text_styles.insert(
-                    egui::TextStyle::Heading,
-                    egui::FontId::new(20.0, egui::FontFamily::Monospace),
-                );
-            });
+            self.apply_styles(ctx);
             self.styles_initialized = true;
         }
 
-        // Check for hotkey toggle events
+        // Check for hotkey events
         let mut hotkey_pressed = false;
-        while self.hotkey_receiver.try_recv().is_ok() {
+        while let Ok(action) = self.hotkey_receiver.try_recv() {
             hotkey_pressed = true;
-            if let Ok(mut visible) = self.visible.lock() {
-                *visible = !*visible;
-                if *visible {
-                    // Show the window by moving it back to its normal position
-                    ctx.send_viewport_cmd(egui::ViewportCommand::OuterPosition(egui::pos2(
-                        SCREEN_WIDTH - 360.0 * 1.3,
-                        55.0,
-                    )));
-                    ctx.send_viewport_cmd(egui::ViewportCommand::InnerSize(egui::vec2(
-                        355.0 * 1.3,
-                        195.0 * 1.2,
-                    )));
-                } else {
-                    // Hide the window by moving it offscreen and making it tiny
-                    ctx.send_viewport_cmd(egui::ViewportCommand::OuterPosition(egui::pos2(
-                        -10000.0, -10000.0,
-                    )));
-                    ctx.send_viewport_cmd(egui::ViewportCommand::InnerSize(egui::vec2(1.0, 1.0)));
-                }
-                println!("Toggling overlay visibility to: {}", *visible);
-            }
+            self.handle_hotkey(ctx, action);
         }
 
-        // Get current visibility state
-        let is_visible = if let Ok(visible) = self.visible.lock() {
-            *visible
-        } else {
-            true
-        };
+        self.apply_config_changes(ctx);
+        self.icons.poll(ctx);
+
+        // First frame, resolution or scaling changed, or the overlay got dragged to another monitor
+        let monitor = ctx.input(|i| {
+            let viewport = i.viewport();
+            viewport
+                .monitor_size
+                .map(|size| egui::Rect::from_min_size(egui::Pos2::ZERO, size))
+                .zip(viewport.native_pixels_per_point)
+        });
+        if let Some((rect, scale)) = monitor
+            && monitor != self.monitor
+        {
+            println!(
+                "Monitor is {}x{} points at {}x scale",
+                rect.width(),
+                rect.height(),
+                scale
+            );
+            self.monitor = monitor;
+        }
+        self.place_main_window(ctx);
 
         // Only request repaint when data changes or hotkey is pressed (better performance)
         if self.data_changed || hotkey_pressed {
             ctx.request_repaint();
             self.data_changed = false;
-        } else if is_visible {
+        } else if self.visibility.is_visible() {
             ctx.request_repaint_after(std::time::Duration::from_millis(200));
         } else {
             // When hidden, repaint much less frequently to save CPU
@@ -125,31 +511,104 @@ impl eframe::App for OverlayApp {
         }
 
         // Try to receive new game data without blocking
-        while let Ok(new_game_info) = self.game_data_receiver.try_recv() {
-            if let Ok(mut game_info) = self.game_info.lock() {
-                *game_info = Some(new_game_info);
-                self.data_changed = true;
+        while let Ok(update) = self.game_data_receiver.try_recv() {
+            match update {
+                GameUpdate::Info(mut new_game_info) => {
+                    self.visibility.data_received(ctx.input(|i| i.time));
+                    if let Some(dragontail) = &self.dragontail {
+                        dragontail.fill_abilities(&mut new_game_info.all_players);
+                    }
+                    if let Ok(mut game_info) = self.game_info.lock() {
+                        *game_info = Some(*new_game_info);
+                    }
+                }
+                // The parts that changed since, there's nothing to put them in before an Info
+                GameUpdate::ActivePlayer(active_player) => {
+                    self.visibility.data_received(ctx.input(|i| i.time));
+                    if let Ok(mut game_info) = self.game_info.lock()
+                        && let Some(game_info) = game_info.as_mut()
+                    {
+                        game_info.active_player = *active_player;
+                    }
+                }
+                GameUpdate::Players(mut players) => {
+                    self.visibility.data_received(ctx.input(|i| i.time));
+                    if let Some(dragontail) = &self.dragontail {
+                        dragontail.fill_abilities(&mut players);
+                    }
+                    if let Ok(mut game_info) = self.game_info.lock()
+                        && let Some(game_info) = game_info.as_mut()
+                    {
+                        game_info.all_players = players;
+                    }
+                }
+                GameUpdate::GameData(game_data) => {
+                    self.visibility.data_received(ctx.input(|i| i.time));
+                    if let Ok(mut game_info) = self.game_info.lock()
+                        && let Some(game_info) = game_info.as_mut()
+                    {
+                        game_info.game_data = game_data;
+                    }
+                }
+                GameUpdate::Events(events) => {
+                    for event in events {
+                        self.objectives.record(&event);
+                        if self.recent_events.len() == MAX_RECENT_EVENTS {
+                            self.recent_events.pop_front();
+                        }
+                        self.recent_events.push_back(event);
+                    }
+                }
+                GameUpdate::EventsReset => {
+                    self.recent_events.clear();
+                    self.objectives.clear();
+                    self.highlights.clear();
+                }
+                GameUpdate::Connection(status) => {
+                    // Stats from the last game shouldn't hang around once it's gone
+                    if matches!(
+                        status.state,
+                        ConnectionState::NoClient | ConnectionState::Loading
+                    ) && let Ok(mut game_info) = self.game_info.lock()
+                    {
+                        *game_info = None;
+                    }
+                    self.connection = Some(status);
+                }
             }
+            self.data_changed = true;
         }
 
-        // Only show UI content when visible, but keep the window running
-        if is_visible {
-            self.render_ui(ctx);
+        let game_state = self.game_state();
+        self.visibility
+            .update(&self.config.visibility, &game_state, ctx.input(|i| i.time));
+
+        // Hidden windows keep running, they're just not drawn into
+        let opacity = self.fade_main_window(ctx);
+        if opacity > 0.0 {
+            self.render_ui(ctx, opacity);
         }
+        self.render_panel_windows(ctx);
 
         ctx.request_repaint();
     }
 }
 
 impl OverlayApp {
-    fn render_ui(&mut self, ctx: &egui::Context) {
+    fn render_ui(&mut self, ctx: &egui::Context, opacity: f32) {
         egui::CentralPanel::default()
             .frame(Frame::default().fill(Color32::TRANSPARENT))
             .show(ctx, |ui| {
+                ui.multiply_opacity(opacity);
                 ui.vertical_centered(|ui| {
                     Frame {
                         corner_radius: egui::CornerRadius::same(10),
-                        fill: egui::Color32::TRANSPARENT,
+                        // The menu has to be readable over the game
+                        fill: if self.settings.is_some() {
+                            egui::Color32::from_black_alpha(230)
+                        } else {
+                            egui::Color32::TRANSPARENT
+                        },
                         stroke: egui::Stroke::new(
                             2.0,
                             egui::Color32::from_rgba_premultiplied(255, 255, 255, 150),
@@ -159,263 +618,301 @@ impl OverlayApp {
                         ..Default::default()
                     }
                     .show(ui, |ui| {
-                        self.render_game_stats(ui);
+                        if let Some(menu) = &mut self.settings {
+                            if menu.show(ui) {
+                                self.close_settings(ui.ctx());
+                            }
+                            return;
+                        }
+                        if self.is_shown(PanelKind::Stats) {
+                            self.render_game_stats(ui);
+                        }
+                        let now = ui.input(|i| i.time);
+                        if let Some((text, until)) = &self.toast {
+                            if now < *until {
+                                ui.colored_label(Color32::GOLD, RichText::new(text).small());
+                            } else {
+                                self.toast = None;
+                            }
+                        }
+                        if self.config.live_client.insecure {
+                            ui.colored_label(
+                                Color32::from_rgb(255, 165, 0),
+                                RichText::new("⚠ Certificate checks off (--insecure)").small(),
+                            );
+                        }
+                        if let Some(error) = &self.config_error {
+                            ui.colored_label(
+                                Color32::from_rgb(255, 80, 80),
+                                RichText::new(format!("Config: {}", error)).small(),
+                            );
+                        }
                     });
                 });
             });
+        if let Some(layout) = &mut self.layout {
+            layout.handles(ctx, PanelKind::Stats);
+        }
     }
 
     fn render_game_stats(&mut self, ui: &mut egui::Ui) {
+        let connection = self.connection.clone();
+        let showing_game = connection.as_ref().is_none_or(|status| {
+            matches!(
+                status.state,
+                ConnectionState::InGame | ConnectionState::PostGame
+            )
+        });
+
         // Get display data
-        let display_data = if let Ok(game_info_guard) = self.game_info.lock() {
-            if let Some(ref game_info) = *game_info_guard {
-                let player = &game_info.active_player;
-                let game_time_minutes = game_info.game_data.game_time / 60.0;
-
-                if let Some(default_info) = game_info
-                    .all_players
-                    .iter()
-                    .find(|p| p.riot_id == player.riot_id)
-                {
-                    let cs_per_min = default_info.scores.creep_score as f64 / game_time_minutes;
-                    let item_gold: f64 = default_info
-                        .items
-                        .iter()
-                        .map(|item| item.price as f64)
-                        .sum();
-                    let total_gold = player.current_gold + item_gold;
-
-                    Some((
-                        player.riot_id.clone(),
-                        cs_per_min,
-                        total_gold,
-                        default_info.is_dead,
-                        default_info.respawn_timer as f64,
-                        player.champion_stats.clone(),
-                    ))
-                } else {
-                    None
-                }
-            } else {
-                None
-            }
+        let display_data = if let Ok(game_info_guard) = self.game_info.lock()
+            && let Some(ref game_info) = *game_info_guard
+            && let Some(panel) = &self.panel
+            && let Some(vars) = stat_rows::variables(game_info)
+        {
+            Some(PlayerView {
+                riot_id: game_info.active_player.riot_id.clone(),
+                is_dead: vars["is_dead"] != 0.0,
+                respawn_timer: vars["respawn_timer"],
+                vars: panel.vars(vars),
+            })
         } else {
             None
         };
 
-        if let Some((riot_id, cs_per_min, total_gold, is_dead, respawn_timer, stats)) = display_data
-        {
+        if showing_game && let Some(view) = display_data {
+            let panel = &self.config.panel;
             ui.vertical(|ui| {
                 ui.vertical_centered(|ui| {
-                    let player_name = riot_id.split('#').next().unwrap_or("Unknown");
+                    let player_name = view.riot_id.split('#').next().unwrap_or("Unknown");
                     ui.colored_label(
-                        Color32::MAGENTA,
-                        egui::RichText::new(format!("Do Not Tilt UwU | {}", player_name))
+                        panel.title_color.0,
+                        egui::RichText::new(format!("{} | {}", panel.title, player_name))
                             .heading()
                             .strong(),
                     );
                 });
 
+                if let Some(status) = &connection
+                    && status.state == ConnectionState::PostGame
+                {
+                    ui.vertical_centered(|ui| {
+                        ui.colored_label(Color32::GRAY, status.state.description());
+                    });
+                }
+
                 ui.separator();
                 ui.add_space(2.0);
 
                 ui.horizontal_centered(|ui| {
                     ui.columns(2, |columns| {
-                        self.render_left_column(&mut columns[0], &stats, cs_per_min);
-                        self.render_right_column(
-                            &mut columns[1],
-                            &stats,
-                            total_gold,
-                            is_dead,
-                            respawn_timer,
-                        );
+                        if let Some(rows) = &self.panel {
+                            self.render_column(&mut columns[0], &rows.left, &view, false);
+                            self.render_column(&mut columns[1], &rows.right, &view, true);
+                        }
                     });
                 });
+
+                if panel.inventory {
+                    self.render_inventory(ui, &view.riot_id);
+                }
             });
         } else {
-            ui.vertical_centered(|ui| {
-                ui.label(RichText::new("Waiting for game data...").strong());
+            ui.vertical_centered(|ui| match &connection {
+                Some(status) => {
+                    ui.label(RichText::new(status.state.description()).strong());
+                    if let Some(error) = &status.error {
+                        ui.colored_label(Color32::GRAY, error);
+                    }
+                }
+                None => {
+                    ui.label(RichText::new("Waiting for game data...").strong());
+                }
             });
         }
     }
 
-    fn render_left_column(
-        &self,
-        ui: &mut egui::Ui,
-        stats: &crate::data::players::ChampionStats,
-        cs_per_min: f64,
-    ) {
-        Frame {
-            corner_radius: egui::CornerRadius::same(8),
-            fill: egui::Color32::from_rgba_premultiplied(0, 0, 0, 80),
-            stroke: egui::Stroke::new(
-                2.0,
-                egui::Color32::from_rgba_premultiplied(128, 128, 128, 150),
-            ),
-            inner_margin: egui::Margin::same(8),
-            ..Default::default()
+    fn render_inventory(&self, ui: &mut egui::Ui, riot_id: &str) {
+        let Ok(game_info) = self.game_info.lock() else {
+            return;
+        };
+        let Some(player) = game_info
+            .as_ref()
+            .and_then(|game_info| game_info.all_players.iter().find(|p| p.riot_id == riot_id))
+        else {
+            return;
+        };
+        ui.add_space(2.0);
+        ui.vertical_centered(|ui| {
+            inventory::render(ui, &self.config.panel, &player.items, &self.icons);
+        });
+    }
+
+    // Every panel but stats in an always on top window of its own, placed like the main one
+    fn render_panel_windows(&mut self, ctx: &egui::Context) {
+        let Some((monitor, _)) = self.monitor else {
+            return;
+        };
+        for kind in PanelKind::ALL {
+            if kind == PanelKind::Stats {
+                continue;
+            }
+            let opacity = visibility::fade(
+                ctx,
+                egui::Id::new(kind.name()),
+                self.is_shown(kind),
+                &self.config.visibility,
+            );
+            // Never made until it's first wanted, then only hidden so it comes back where it was
+            if opacity > 0.0 && !self.panel_windows.contains(&kind) {
+                self.panel_windows.push(kind);
+            }
+            if !self.panel_windows.contains(&kind) {
+                continue;
+            }
+            let window = self.config.windows.get(kind);
+            let size = window.size();
+            let viewport = egui::ViewportBuilder::default()
+                .with_title(kind.title())
+                .with_inner_size(size)
+                .with_position(window.placement().position(monitor, size))
+                .with_decorations(false)
+                .with_transparent(true)
+                .with_always_on_top()
+                .with_taskbar(false)
+                .with_visible(opacity > 0.0)
+                .with_mouse_passthrough(!self.interactive && self.layout.is_none());
+            ctx.show_viewport_immediate(
+                egui::ViewportId::from_hash_of(kind.name()),
+                viewport,
+                |ctx, _| {
+                    if opacity == 0.0 {
+                        return;
+                    }
+                    egui::CentralPanel::default()
+                        .frame(Frame::default().fill(Color32::TRANSPARENT))
+                        .show(ctx, |ui| {
+                            ui.multiply_opacity(opacity);
+                            self.render_panel(ui, kind);
+                        });
+                    if let Some(layout) = &mut self.layout {
+                        layout.handles(ctx, kind);
+                    }
+                },
+            );
         }
-        .show(ui, |ui| {
-            for (label, icon, value, icon_color) in [
-                (
-                    "Attack Damage:",
-                    "⚔",
-                    format!("{:.0}", stats.attack_damage),
-                    Color32::from_rgb(255, 100, 100),
-                ),
-                (
-                    "Ability Power:",
-                    "✨",
-                    format!("{:.0}", stats.ability_power),
-                    Color32::from_rgb(100, 150, 255),
-                ),
-                (
-                    "Armor:",
-                    "🛡",
-                    format!("{:.0}", stats.armor),
-                    Color32::from_rgb(200, 200, 100),
-                ),
-                (
-                    "Magic Resist:",
-                    "🔮",
-                    format!("{:.0}", stats.magic_resist),
-                    Color32::from_rgb(150, 100, 255),
-                ),
-                (
-                    "CS/min:",
-                    "🗡",
-                    format!("{:.1}", cs_per_min),
-                    Color32::from_rgb(255, 215, 0),
-                ),
-                (
-                    "Move Speed: ",
-                    "💨",
-                    format!("{:.0}", stats.move_speed),
-                    Color32::from_rgb(100, 255, 100),
-                ),
-                (
-                    "Crit Chance:",
-                    "💥",
-                    format!("{:.0}%", stats.crit_chance),
-                    Color32::from_rgb(255, 165, 0),
-                ),
-            ] {
-                self.render_stat_row(ui, label, icon, &value, icon_color);
+    }
+
+    fn render_panel(&self, ui: &mut egui::Ui, kind: PanelKind) {
+        let panel = &self.config.panel;
+        let Ok(game_info) = self.game_info.lock() else {
+            return;
+        };
+        panels::frame(panel).show(ui, |ui| {
+            ui.set_min_size(ui.available_size());
+            let Some(game_info) = game_info.as_ref() else {
+                ui.label(RichText::new("Waiting for game data...").strong());
+                return;
+            };
+            match kind {
+                PanelKind::Stats => {}
+                PanelKind::Enemies => panels::render_enemies(ui, panel, game_info, &self.icons),
+                PanelKind::Objectives => {
+                    panels::render_objectives(ui, panel, game_info, &self.objectives)
+                }
+                PanelKind::Toasts => {
+                    // Highlights go in among the game's own events
+                    let events = self
+                        .recent_events
+                        .iter()
+                        .map(|event| (event.event_time, event.kind.summary(), panel.value_color.0))
+                        .chain(
+                            self.highlights
+                                .iter()
+                                .map(|time| (*time, "★ Highlight".to_string(), Color32::GOLD)),
+                        );
+                    panels::render_toasts(ui, panel, game_info, events);
+                }
+                PanelKind::Scoreboard => {
+                    panels::render_scoreboard(ui, panel, game_info, &self.icons)
+                }
             }
         });
     }
 
-    fn render_right_column(
+    fn render_column(
         &self,
         ui: &mut egui::Ui,
-        stats: &crate::data::players::ChampionStats,
-        total_gold: f64,
-        is_dead: bool,
-        respawn_timer: f64,
+        rows: &[CompiledRow],
+        view: &PlayerView,
+        show_respawn: bool,
     ) {
-        Frame {
-            corner_radius: egui::CornerRadius::same(8),
-            fill: egui::Color32::from_rgba_premultiplied(0, 0, 0, 80),
-            stroke: egui::Stroke::new(
-                2.0,
-                egui::Color32::from_rgba_premultiplied(128, 128, 128, 150),
-            ),
-            inner_margin: egui::Margin::same(8),
-            ..Default::default()
-        }
-        .show(ui, |ui| {
-            for (label, icon, value, icon_color) in [
-                (
-                    "Lethality:",
-                    "",
-                    if (stats.lethality == 0.0 || stats.physical_lethality == 0.0)
-                        && stats.armor_pen == 1.0
-                    {
-                        "Farm!!!".to_string()
-                    } else {
-                        format!(
-                            "{:.0} | %{:.2}",
-                            stats.lethality,
-                            100.0 * (1.0 - stats.armor_pen)
-                        )
-                    },
-                    Color32::from_rgb(255, 80, 80),
-                ),
-                (
-                    "Mag Pen:",
-                    "",
-                    if (stats.magic_lethality + stats.magic_pen) == 0.0
-                        && stats.magic_pen_percent == 1.0
-                    {
-                        "Keep it up!".to_string()
-                    } else {
-                        format!(
-                            "{:.0} | %{:.2}",
-                            stats.magic_lethality + stats.magic_pen,
-                            100.0 * (1.0 - stats.magic_pen_percent)
-                        )
-                    },
-                    Color32::PURPLE,
-                ),
-                (
-                    "Att. Sp.:",
-                    "",
-                    format!("{:.2} atk/s", stats.attack_speed),
-                    Color32::from_rgb(255, 255, 100),
-                ),
-                (
-                    "HP Regen:",
-                    "",
-                    format!("{:.1} hp/s", stats.health_regen_rate),
-                    Color32::from_rgb(100, 255, 100),
-                ),
-                (
-                    "Life Steal:",
-                    "❤",
-                    format!("{:.0}%", stats.life_steal),
-                    Color32::from_rgb(255, 100, 100),
-                ),
-                (
-                    "Total Gold:",
-                    "💰",
-                    format!("{:.0}", total_gold),
-                    Color32::from_rgb(255, 215, 0),
-                ),
-            ] {
-                self.render_stat_row(ui, label, icon, &value, icon_color);
+        panels::frame(&self.config.panel).show(ui, |ui| {
+            let time = ui.input(|i| i.time);
+            for row in rows.iter().filter(|row| row.config.visible) {
+                let value = row.text(&view.vars);
+                let value_color = match row.rule(&view.vars) {
+                    Some(rule) => {
+                        let color = rule.color.unwrap_or(self.config.panel.value_color).0;
+                        if rule.flash {
+                            flash(color, time)
+                        } else {
+                            color
+                        }
+                    }
+                    None => self.config.panel.value_color.0,
+                };
+                self.render_stat_row(ui, &row.config, &value, value_color);
             }
 
-            ui.horizontal(|ui| {
-                if is_dead && respawn_timer > 0.0 {
-                    ui.colored_label(Color32::RED, RichText::new("DEAD:").strong());
-                    ui.colored_label(
-                        Color32::GRAY,
-                        RichText::new(format!("{:.1}s", respawn_timer)).strong(),
-                    );
-                } else {
-                    ui.colored_label(Color32::GREEN, RichText::new("ALIVE").strong());
-                }
-            });
+            if show_respawn {
+                ui.horizontal(|ui| {
+                    if view.is_dead && view.respawn_timer > 0.0 {
+                        ui.colored_label(Color32::RED, RichText::new("DEAD:").strong());
+                        ui.colored_label(
+                            Color32::GRAY,
+                            RichText::new(format!("{:.1}s", view.respawn_timer)).strong(),
+                        );
+                    } else {
+                        ui.colored_label(Color32::GREEN, RichText::new("ALIVE").strong());
+                    }
+                });
+            }
         });
     }
 
     fn render_stat_row(
         &self,
         ui: &mut egui::Ui,
-        label: &str,
-        icon: &str,
+        row: &RowConfig,
         value: &str,
-        icon_color: Color32,
+        value_color: Color32,
     ) {
+        let panel = &self.config.panel;
         ui.horizontal(|ui| {
-            ui.colored_label(Color32::MAGENTA, RichText::new(label).strong());
+            ui.colored_label(panel.label_color.0, RichText::new(&row.label).strong());
 
             ui.with_layout(Layout::right_to_left(egui::Align::Center), |ui| {
                 ui.horizontal(|ui| {
-                    ui.label(RichText::new(value).color(Color32::GRAY).strong());
-                    ui.label(RichText::new(icon).color(icon_color).size(16.0).strong());
+                    ui.label(RichText::new(value).color(value_color).strong());
+                    // The emoji until the image is loaded, or if there's no such image
+                    let image = Icon::Stat(row.image.clone());
+                    if row.image.is_empty() || !self.icons.show(ui, &image, 16.0) {
+                        ui.label(
+                            RichText::new(&row.icon)
+                                .color(row.color.0)
+                                .size(16.0)
+                                .strong(),
+                        );
+                    }
                 });
             });
         });
     }
 }
+
+// Pulses between the full color and a faint one, about once a second
+fn flash(color: Color32, time: f64) -> Color32 {
+    let strength = 0.6 + 0.4 * (time * std::f64::consts::TAU).sin();
+    color.gamma_multiply(strength as f32)
+}
@@ -0,0 +1,341 @@
+use crate::config::PanelConfig;
+use crate::icons::{Icon, IconCache};
+use crate::inventory;
+use eframe::egui;
+use egui::{Color32, Frame, RichText};
+use league_overlay::GameInfo;
+use league_overlay::data::events::{Event, EventKind};
+use league_overlay::data::players::Player;
+
+/*
+The overlay's panels. Stats is the main overlay window, the rest each get a window of their own
+(an egui viewport, see overlay.rs) placed by their [windows.<panel>] section of the config.
+They all draw from the same GameInfo and events, a panel is just a way of looking at them.
+*/
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum PanelKind {
+    Stats,
+    Enemies,
+    Objectives,
+    Toasts,
+    Scoreboard,
+}
+
+impl PanelKind {
+    pub const ALL: [PanelKind; 5] = [
+        PanelKind::Stats,
+        PanelKind::Enemies,
+        PanelKind::Objectives,
+        PanelKind::Toasts,
+        PanelKind::Scoreboard,
+    ];
+
+    /// Its section under [windows] in the config
+    pub fn name(&self) -> &'static str {
+        match self {
+            PanelKind::Stats => "stats",
+            PanelKind::Enemies => "enemies",
+            PanelKind::Objectives => "objectives",
+            PanelKind::Toasts => "toasts",
+            PanelKind::Scoreboard => "scoreboard",
+        }
+    }
+
+    pub fn title(&self) -> &'static str {
+        match self {
+            PanelKind::Stats => "My stats",
+            PanelKind::Enemies => "Enemy timers",
+            PanelKind::Objectives => "Objective timers",
+            PanelKind::Toasts => "Event toasts",
+            PanelKind::Scoreboard => "Scoreboard",
+        }
+    }
+}
+
+//How long an event stays up in the toasts panel, in game seconds
+const TOAST_SECONDS: f64 = 8.0;
+//Most toasts shown at once
+const TOASTS_SHOWN: usize = 4;
+
+/// Background for the panels that get a window of their own
+pub fn frame(panel: &PanelConfig) -> Frame {
+    Frame {
+        corner_radius: egui::CornerRadius::same(8),
+        fill: Color32::from_black_alpha((panel.opacity * 255.0).round() as u8),
+        stroke: egui::Stroke::new(2.0, Color32::from_rgba_premultiplied(128, 128, 128, 150)),
+        inner_margin: egui::Margin::same(8),
+        ..Default::default()
+    }
+}
+
+fn heading(ui: &mut egui::Ui, panel: &PanelConfig, text: &str) {
+    ui.vertical_centered(|ui| {
+        ui.colored_label(panel.title_color.0, RichText::new(text).heading().strong());
+    });
+    ui.separator();
+}
+
+fn our_team(game_info: &GameInfo) -> Option<&str> {
+    game_info
+        .all_players
+        .iter()
+        .find(|p| p.riot_id == game_info.active_player.riot_id)
+        .map(|p| p.team.as_str())
+}
+
+pub fn render_enemies(
+    ui: &mut egui::Ui,
+    panel: &PanelConfig,
+    game_info: &GameInfo,
+    icons: &IconCache,
+) {
+    heading(ui, panel, "Enemies");
+    let our_team = our_team(game_info);
+
+    let enemies: Vec<_> = game_info
+        .all_players
+        .iter()
+        .filter(|p| Some(p.team.as_str()) != our_team)
+        .collect();
+    let mut with_items = Vec::new();
+    egui::Grid::new("enemies").num_columns(5).show(ui, |ui| {
+        for player in &enemies {
+            ui.horizontal(|ui| {
+                icons.show(ui, &Icon::Champion(player.champion_name.clone()), 18.0);
+                let name = ui
+                    .add(clickable(panel.label_color.0, &player.champion_name))
+                    .on_hover_text("Click for their items");
+                let id = egui::Id::new(("enemy_items", &player.riot_id));
+                if inventory::expanded(ui, &name, id) {
+                    with_items.push(*player);
+                }
+            });
+            ui.colored_label(panel.value_color.0, format!("Lv {}", player.level));
+            let scores = &player.scores;
+            ui.colored_label(
+                panel.value_color.0,
+                format!("{}/{}/{}", scores.kills, scores.deaths, scores.assists),
+            );
+            if player.is_dead {
+                ui.colored_label(
+                    Color32::RED,
+                    RichText::new(format!("{:.0}s", player.respawn_timer)).strong(),
+                );
+            } else {
+                ui.colored_label(Color32::GREEN, "alive");
+            }
+            // Nothing at all without icons, the names would crowd the panel
+            ui.horizontal(|ui| {
+                icons.show(ui, &Icon::Rune(player.runes.keystone.id), 16.0);
+                for spell in [
+                    &player.spells.summoner_spell_one,
+                    &player.spells.summoner_spell_two,
+                ] {
+                    if let Some(id) = spell.id() {
+                        icons.show(ui, &Icon::SummonerSpell(id.to_string()), 16.0);
+                    }
+                }
+            });
+            ui.end_row();
+        }
+    });
+    players_items(ui, panel, &with_items, icons);
+}
+
+// A label that takes clicks, while the overlay takes the mouse
+fn clickable(color: Color32, text: &str) -> egui::Label {
+    egui::Label::new(RichText::new(text).color(color).strong()).sense(egui::Sense::click())
+}
+
+// The items of everyone whose were asked for, under the panel
+fn players_items(ui: &mut egui::Ui, panel: &PanelConfig, players: &[&Player], icons: &IconCache) {
+    if players.is_empty() {
+        return;
+    }
+    ui.separator();
+    for player in players {
+        ui.horizontal(|ui| {
+            if !icons.show(ui, &Icon::Champion(player.champion_name.clone()), 18.0) {
+                ui.colored_label(panel.label_color.0, &player.champion_name);
+            }
+            inventory::render(ui, panel, &player.items, icons);
+        });
+    }
+}
+
+struct Objective {
+    name: &'static str,
+    first_spawn: f64,
+    //None for the ones that don't come back once taken
+    respawn: Option<f64>,
+}
+
+// Summoner's Rift timers, in game seconds
+const OBJECTIVES: [Objective; 5] = [
+    Objective {
+        name: "Dragon",
+        first_spawn: 5.0 * 60.0,
+        respawn: Some(5.0 * 60.0),
+    },
+    Objective {
+        name: "Void Grubs",
+        first_spawn: 8.0 * 60.0,
+        respawn: None,
+    },
+    Objective {
+        name: "Herald",
+        first_spawn: 15.0 * 60.0,
+        respawn: None,
+    },
+    Objective {
+        name: "Atakhan",
+        first_spawn: 20.0 * 60.0,
+        respawn: None,
+    },
+    Objective {
+        name: "Baron",
+        first_spawn: 25.0 * 60.0,
+        respawn: Some(6.0 * 60.0),
+    },
+];
+
+/// When each of OBJECTIVES was last taken, kept apart from the recent events so none get lost
+#[derive(Debug, Default)]
+pub struct ObjectiveTimers {
+    taken: [Option<f64>; 5],
+}
+
+impl ObjectiveTimers {
+    pub fn record(&mut self, event: &Event) {
+        let index = match event.kind {
+            EventKind::DragonKill { .. } => 0,
+            EventKind::HordeKill { .. } => 1,
+            EventKind::HeraldKill { .. } => 2,
+            EventKind::AtakhanKill { .. } => 3,
+            EventKind::BaronKill { .. } => 4,
+            _ => return,
+        };
+        self.taken[index] = Some(event.event_time);
+    }
+
+    pub fn clear(&mut self) {
+        *self = Self::default();
+    }
+}
+
+pub fn render_objectives(
+    ui: &mut egui::Ui,
+    panel: &PanelConfig,
+    game_info: &GameInfo,
+    timers: &ObjectiveTimers,
+) {
+    heading(ui, panel, "Objectives");
+    let now = game_info.game_data.game_time;
+
+    egui::Grid::new("objectives").num_columns(2).show(ui, |ui| {
+        for (objective, taken) in OBJECTIVES.iter().zip(timers.taken) {
+            ui.colored_label(panel.label_color.0, RichText::new(objective.name).strong());
+            let spawns = match (taken, objective.respawn) {
+                (None, _) => Some(objective.first_spawn),
+                (Some(time), Some(respawn)) => Some(time + respawn),
+                (Some(_), None) => None,
+            };
+            match spawns {
+                Some(spawns) if spawns > now => {
+                    ui.colored_label(panel.value_color.0, clock(spawns - now));
+                }
+                Some(_) => {
+                    ui.colored_label(Color32::GREEN, RichText::new("up").strong());
+                }
+                None => {
+                    ui.colored_label(Color32::GRAY, "taken");
+                }
+            }
+            ui.end_row();
+        }
+    });
+}
+
+/// The latest events (and highlights) for a few seconds each, newest on top
+pub fn render_toasts(
+    ui: &mut egui::Ui,
+    panel: &PanelConfig,
+    game_info: &GameInfo,
+    events: impl Iterator<Item = (f64, String, Color32)>,
+) {
+    let now = game_info.game_data.game_time;
+    let mut toasts: Vec<(f64, String, Color32)> = events
+        .filter(|(time, _, _)| (now - TOAST_SECONDS..=now).contains(time))
+        .collect();
+    toasts.sort_by(|a, b| b.0.total_cmp(&a.0));
+    for (time, text, color) in toasts.into_iter().take(TOASTS_SHOWN) {
+        ui.horizontal(|ui| {
+            ui.colored_label(panel.label_color.0, clock(time));
+            ui.colored_label(color, RichText::new(text).strong());
+        });
+    }
+}
+
+/// Kills, item gold and average level for both teams, ours first
+pub fn render_scoreboard(
+    ui: &mut egui::Ui,
+    panel: &PanelConfig,
+    game_info: &GameInfo,
+    icons: &IconCache,
+) {
+    let ours = our_team(game_info).unwrap_or("ORDER");
+    let mut teams: Vec<&str> = Vec::new();
+    for player in &game_info.all_players {
+        if !teams.contains(&player.team.as_str()) {
+            teams.push(&player.team);
+        }
+    }
+    teams.sort_by_key(|team| *team != ours);
+
+    ui.vertical_centered(|ui| {
+        ui.colored_label(
+            panel.title_color.0,
+            RichText::new(clock(game_info.game_data.game_time)).strong(),
+        );
+    });
+    let mut with_items = Vec::new();
+    egui::Grid::new("scoreboard").num_columns(4).show(ui, |ui| {
+        for team in teams {
+            let players: Vec<_> = game_info
+                .all_players
+                .iter()
+                .filter(|p| p.team == team)
+                .collect();
+            let kills: u32 = players.iter().map(|p| p.scores.kills as u32).sum();
+            let gold: u32 = players
+                .iter()
+                .flat_map(|p| &p.items)
+                .map(|item| item.price)
+                .sum();
+            let level = players.iter().map(|p| p.level as f64).sum::<f64>() / players.len() as f64;
+
+            let name = if team == ours { "Us" } else { "Them" };
+            let label = ui
+                .add(clickable(panel.label_color.0, name))
+                .on_hover_text("Click for their items");
+            if inventory::expanded(ui, &label, egui::Id::new(("team_items", team))) {
+                with_items.extend(&players);
+            }
+            ui.colored_label(panel.value_color.0, format!("{} kills", kills));
+            ui.colored_label(
+                panel.value_color.0,
+                format!("{:.1}k gold", gold as f64 / 1000.0),
+            );
+            ui.colored_label(panel.value_color.0, format!("Lv {:.1}", level));
+            ui.end_row();
+        }
+    });
+    players_items(ui, panel, &with_items, icons);
+}
+
+/// Game seconds as m:ss
+pub fn clock(seconds: f64) -> String {
+    let seconds = seconds.max(0.0) as u64;
+    format!("{}:{:02}", seconds / 60, seconds % 60)
+}
@@ -0,0 +1,287 @@
+use anyhow::{Context, Result, bail};
+use eframe::egui::{Pos2, Rect, Vec2, vec2};
+use serde::{Deserialize, Serialize};
+use std::str::FromStr;
+
+/*
+Where the overlay sits on screen. It's pinned to a corner (or the middle of an edge) of the monitor
+and pushed in from there by an offset, so it ends up in the same spot on any resolution.
+The monitor size (in points, so display scaling is already taken care of) only comes from egui once
+the window is up, and the overlay checks it every frame to move back into place when it changes.
+Positions are from the top left of the primary monitor, so a monitor to its left or above it has a
+negative origin. egui only gives the size, so the overlay goes by a monitor at (0, 0).
+Dragging a panel in the edit layout mode (see layout.rs) goes the other way: from_rect works out
+the anchor and offset from where it was dropped, so it keeps its spot on other resolutions too.
+Set per panel with anchor/offset in the [windows.<panel>] sections of the config, and for the stats
+panel also with --anchor top-right --offset 6,55
+*/
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Anchor {
+    TopLeft,
+    Top,
+    #[default]
+    TopRight,
+    Left,
+    Right,
+    BottomLeft,
+    Bottom,
+    BottomRight,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Edge {
+    Start,
+    Middle,
+    End,
+}
+
+impl Anchor {
+    pub const ALL: [Anchor; 8] = [
+        Anchor::TopLeft,
+        Anchor::Top,
+        Anchor::TopRight,
+        Anchor::Left,
+        Anchor::Right,
+        Anchor::BottomLeft,
+        Anchor::Bottom,
+        Anchor::BottomRight,
+    ];
+
+    /// Same as in the config and on the command line
+    pub fn name(&self) -> &'static str {
+        match self {
+            Anchor::TopLeft => "top-left",
+            Anchor::Top => "top",
+            Anchor::TopRight => "top-right",
+            Anchor::Left => "left",
+            Anchor::Right => "right",
+            Anchor::BottomLeft => "bottom-left",
+            Anchor::Bottom => "bottom",
+            Anchor::BottomRight => "bottom-right",
+        }
+    }
+
+    fn from_edges(horizontal: Edge, vertical: Edge) -> Anchor {
+        Anchor::ALL
+            .into_iter()
+            .find(|anchor| anchor.edges() == (horizontal, vertical))
+            .expect("Every pair of edges but middle/middle is an anchor")
+    }
+
+    fn edges(&self) -> (Edge, Edge) {
+        match self {
+            Anchor::TopLeft => (Edge::Start, Edge::Start),
+            Anchor::Top => (Edge::Middle, Edge::Start),
+            Anchor::TopRight => (Edge::End, Edge::Start),
+            Anchor::Left => (Edge::Start, Edge::Middle),
+            Anchor::Right => (Edge::End, Edge::Middle),
+            Anchor::BottomLeft => (Edge::Start, Edge::End),
+            Anchor::Bottom => (Edge::Middle, Edge::End),
+            Anchor::BottomRight => (Edge::End, Edge::End),
+        }
+    }
+}
+
+impl FromStr for Anchor {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match Anchor::ALL.into_iter().find(|anchor| anchor.name() == s) {
+            Some(anchor) => Ok(anchor),
+            None => bail!(
+                "Unknown anchor {:?} (top-left, top, top-right, left, right, bottom-left, bottom, bottom-right)",
+                s
+            ),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Placement {
+    pub anchor: Anchor,
+    //Points in from the anchored edges, centered axes just shift by it
+    pub offset: Vec2,
+}
+
+impl Default for Placement {
+    // Same spot the overlay has always had, just under the top right corner
+    fn default() -> Self {
+        Self {
+            anchor: Anchor::TopRight,
+            offset: vec2(6.5, 55.0),
+        }
+    }
+}
+
+impl Placement {
+    /// Top left corner of a `window` sized overlay on the `monitor`
+    pub fn position(&self, monitor: Rect, window: Vec2) -> Pos2 {
+        let (horizontal, vertical) = self.anchor.edges();
+        monitor.min
+            + vec2(
+                along(horizontal, monitor.width(), window.x, self.offset.x),
+                along(vertical, monitor.height(), window.y, self.offset.y),
+            )
+    }
+}
+
+impl Placement {
+    /// Anchored to whichever corner or edge a window at `rect` is closest to, edges within
+    /// SNAP_DISTANCE pull it in flush. Windows dragged past an edge end up back against it
+    pub fn from_rect(monitor: Rect, rect: Rect) -> Placement {
+        // From here on everything is from the monitor's top left
+        let rect = rect.translate(-monitor.min.to_vec2());
+        let monitor = monitor.size();
+        let horizontal = nearest_edge(monitor.x, rect.center().x);
+        let vertical = match (horizontal, nearest_edge(monitor.y, rect.center().y)) {
+            // The middle of the screen isn't an anchor, go by the closer of top and bottom
+            (Edge::Middle, Edge::Middle) if rect.center().y < monitor.y / 2.0 => Edge::Start,
+            (Edge::Middle, Edge::Middle) => Edge::End,
+            (_, vertical) => vertical,
+        };
+        Placement {
+            anchor: Anchor::from_edges(horizontal, vertical),
+            offset: vec2(
+                offset_along(horizontal, monitor.x, rect.width(), rect.min.x),
+                offset_along(vertical, monitor.y, rect.height(), rect.min.y),
+            ),
+        }
+    }
+}
+
+//Points from an edge that count as against it
+const SNAP_DISTANCE: f32 = 16.0;
+
+// By which third of the screen the middle of the window is in
+fn nearest_edge(monitor: f32, center: f32) -> Edge {
+    if center < monitor / 3.0 {
+        Edge::Start
+    } else if center > monitor * 2.0 / 3.0 {
+        Edge::End
+    } else {
+        Edge::Middle
+    }
+}
+
+// The other way round from along()
+fn offset_along(edge: Edge, monitor: f32, window: f32, position: f32) -> f32 {
+    let offset = match edge {
+        Edge::Start => position,
+        Edge::Middle => position - (monitor - window) / 2.0,
+        Edge::End => monitor - window - position,
+    };
+    let snapped = match edge {
+        Edge::Middle => offset.abs() < SNAP_DISTANCE,
+        Edge::Start | Edge::End => offset < SNAP_DISTANCE,
+    };
+    if snapped { 0.0 } else { offset }
+}
+
+fn along(edge: Edge, monitor: f32, window: f32, offset: f32) -> f32 {
+    match edge {
+        Edge::Start => offset,
+        Edge::Middle => (monitor - window) / 2.0 + offset,
+        Edge::End => monitor - window - offset,
+    }
+}
+
+/// "x,y" in points
+pub fn parse_offset(text: &str) -> Result<Vec2> {
+    let (x, y) = text
+        .split_once(',')
+        .with_context(|| format!("Expected x,y for the offset, got {:?}", text))?;
+    Ok(vec2(
+        x.trim().parse().context("Offset x must be a number")?,
+        y.trim().parse().context("Offset y must be a number")?,
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use eframe::egui::pos2;
+
+    // A 1920x1080 monitor to the left of the primary one and a bit higher up
+    fn left_monitor() -> Rect {
+        Rect::from_min_size(pos2(-1920.0, -200.0), vec2(1920.0, 1080.0))
+    }
+
+    const WINDOW: Vec2 = vec2(300.0, 200.0);
+
+    #[test]
+    fn every_anchor_places_on_a_monitor_left_of_the_primary() {
+        let expected = [
+            (Anchor::TopLeft, pos2(-1890.0, -160.0)),
+            (Anchor::Top, pos2(-1080.0, -160.0)),
+            (Anchor::TopRight, pos2(-330.0, -160.0)),
+            (Anchor::Left, pos2(-1890.0, 280.0)),
+            (Anchor::Right, pos2(-330.0, 280.0)),
+            (Anchor::BottomLeft, pos2(-1890.0, 640.0)),
+            (Anchor::Bottom, pos2(-1080.0, 640.0)),
+            (Anchor::BottomRight, pos2(-330.0, 640.0)),
+        ];
+        for (anchor, position) in expected {
+            let placement = Placement {
+                anchor,
+                offset: vec2(30.0, 40.0),
+            };
+            assert_eq!(
+                placement.position(left_monitor(), WINDOW),
+                position,
+                "{}",
+                anchor.name()
+            );
+        }
+    }
+
+    #[test]
+    fn placements_survive_the_round_trip_through_a_rect() {
+        for monitor in [
+            left_monitor(),
+            Rect::from_min_size(Pos2::ZERO, vec2(2560.0, 1440.0)),
+        ] {
+            for anchor in Anchor::ALL {
+                let placement = Placement {
+                    anchor,
+                    offset: vec2(30.0, 40.0),
+                };
+                let rect = Rect::from_min_size(placement.position(monitor, WINDOW), WINDOW);
+                assert_eq!(Placement::from_rect(monitor, rect), placement);
+            }
+        }
+    }
+
+    #[test]
+    fn edges_within_the_snap_distance_pull_the_window_flush() {
+        let monitor = left_monitor();
+        let dropped_at = |x: f32, y: f32| {
+            Placement::from_rect(
+                monitor,
+                Rect::from_min_size(monitor.min + vec2(x, y), WINDOW),
+            )
+        };
+
+        let inside = dropped_at(SNAP_DISTANCE - 0.5, SNAP_DISTANCE);
+        assert_eq!(inside.anchor, Anchor::TopLeft);
+        assert_eq!(inside.offset, vec2(0.0, SNAP_DISTANCE));
+
+        // Past the edge counts as against it
+        assert_eq!(dropped_at(-40.0, -5.0).offset, Vec2::ZERO);
+
+        // The right edge, from the other side
+        let right = dropped_at(1920.0 - 300.0 - 10.0, 100.0);
+        assert_eq!(right.anchor, Anchor::TopRight);
+        assert_eq!(right.offset, vec2(0.0, 100.0));
+
+        // A centered axis snaps to the middle from either side
+        let centered = (1920.0 - 300.0) / 2.0;
+        assert_eq!(dropped_at(centered + 10.0, 100.0).offset, vec2(0.0, 100.0));
+        assert_eq!(dropped_at(centered - 10.0, 100.0).anchor, Anchor::Top);
+        assert_eq!(
+            dropped_at(centered - SNAP_DISTANCE, 100.0).offset,
+            vec2(-SNAP_DISTANCE, 100.0)
+        );
+    }
+}
@@ -0,0 +1,319 @@
+use anyhow::{Context, Result, bail};
+use serde::{Deserialize, Serialize};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tokio::time::Instant;
+
+/*
+How often the live fetcher hits each Live Client endpoint.
+Stats on /activeplayer change all the time so it gets polled the most, the scoreboard and events less.
+While there's no game we only knock on /gamestats, backing off (with jitter) the longer League is away.
+Set in the [poll] section of the config (see config.rs), or overridden with --poll,
+e.g. --poll activeplayer=500,playerlist=3000,backoff_max=60000 (all ms)
+*/
+
+/// An endpoint the live fetcher polls on its own schedule
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Endpoint {
+    /// /activeplayer, your stats
+    ActivePlayer,
+    /// /playerlist, the scoreboard
+    PlayerList,
+    /// /eventdata, the event log
+    Events,
+    /// /gamestats, mode and game time
+    GameStats,
+}
+
+impl Endpoint {
+    /// Every endpoint, in the order Schedule keeps them
+    pub const ALL: [Endpoint; 4] = [
+        Endpoint::ActivePlayer,
+        Endpoint::PlayerList,
+        Endpoint::Events,
+        Endpoint::GameStats,
+    ];
+
+    /// The path under the API's base url
+    pub fn path(&self) -> &'static str {
+        match self {
+            Endpoint::ActivePlayer => "/activeplayer",
+            Endpoint::PlayerList => "/playerlist",
+            Endpoint::Events => "/eventdata",
+            Endpoint::GameStats => "/gamestats",
+        }
+    }
+
+    /// Where this endpoint's response lives in /allgamedata
+    pub fn snapshot_key(&self) -> &'static str {
+        match self {
+            Endpoint::ActivePlayer => "activePlayer",
+            Endpoint::PlayerList => "allPlayers",
+            Endpoint::Events => "events",
+            Endpoint::GameStats => "gameData",
+        }
+    }
+}
+
+/// How often each endpoint gets polled, the `[poll]` section of the config
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct PollConfig {
+    /// Your stats
+    #[serde(rename = "activeplayer", with = "millis")]
+    pub active_player: Duration,
+    /// The scoreboard
+    #[serde(rename = "playerlist", with = "millis")]
+    pub player_list: Duration,
+    /// The event log
+    #[serde(with = "millis")]
+    pub events: Duration,
+    /// Game time, and the check for whether there's a game at all
+    #[serde(rename = "gamestats", with = "millis")]
+    pub game_stats: Duration,
+    /// Waiting for League to show up, doubles from min to max
+    #[serde(with = "millis")]
+    pub backoff_min: Duration,
+    /// The longest wait between tries
+    #[serde(with = "millis")]
+    pub backoff_max: Duration,
+}
+
+impl Default for PollConfig {
+    fn default() -> Self {
+        Self {
+            active_player: Duration::from_millis(250),
+            player_list: Duration::from_millis(2000),
+            events: Duration::from_millis(1000),
+            game_stats: Duration::from_millis(1000),
+            backoff_min: Duration::from_millis(1000),
+            backoff_max: Duration::from_secs(30),
+        }
+    }
+}
+
+impl PollConfig {
+    /// How long to wait between two polls of `endpoint`
+    pub fn interval(&self, endpoint: Endpoint) -> Duration {
+        match endpoint {
+            Endpoint::ActivePlayer => self.active_player,
+            Endpoint::PlayerList => self.player_list,
+            Endpoint::Events => self.events,
+            Endpoint::GameStats => self.game_stats,
+        }
+    }
+
+    /// Applies "key=ms,key=ms" on top of this config
+    pub fn apply_overrides(&mut self, overrides: &str) -> Result<()> {
+        for pair in overrides.split(',').filter(|pair| !pair.trim().is_empty()) {
+            let (key, millis) = pair
+                .split_once('=')
+                .with_context(|| format!("Expected key=ms, got {:?}", pair))?;
+            let millis: u64 = millis
+                .trim()
+                .parse()
+                .with_context(|| format!("{} must be a whole number of milliseconds", key))?;
+
+            let slot = match key.trim() {
+                "activeplayer" => &mut self.active_player,
+                "playerlist" => &mut self.player_list,
+                "events" => &mut self.events,
+                "gamestats" => &mut self.game_stats,
+                "backoff_min" => &mut self.backoff_min,
+                "backoff_max" => &mut self.backoff_max,
+                other => bail!(
+                    "Unknown poll setting {:?} (activeplayer, playerlist, events, gamestats, backoff_min, backoff_max)",
+                    other
+                ),
+            };
+            *slot = Duration::from_millis(millis);
+        }
+
+        if let Some(problem) = self.problems().into_iter().next() {
+            bail!(problem);
+        }
+        Ok(())
+    }
+
+    /// Settings that can't work, empty if everything is fine
+    pub fn problems(&self) -> Vec<String> {
+        let mut problems = Vec::new();
+        for (key, interval) in [
+            ("activeplayer", self.active_player),
+            ("playerlist", self.player_list),
+            ("events", self.events),
+            ("gamestats", self.game_stats),
+            ("backoff_min", self.backoff_min),
+            ("backoff_max", self.backoff_max),
+        ] {
+            if interval.is_zero() {
+                problems.push(format!("{} can't be 0", key));
+            }
+        }
+        if self.backoff_min > self.backoff_max {
+            problems.push("backoff_min can't be more than backoff_max".to_string());
+        }
+        problems
+    }
+}
+
+//Durations are whole milliseconds in the config file
+mod millis {
+    use serde::{Deserialize, Deserializer, Serializer};
+    use std::time::Duration;
+
+    pub fn serialize<S>(duration: &Duration, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.serialize_u64(duration.as_millis() as u64)
+    }
+
+    pub fn deserialize<'de, D>(deserializer: D) -> Result<Duration, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        Ok(Duration::from_millis(u64::deserialize(deserializer)?))
+    }
+}
+
+/// Picks the endpoint that's due next, so an active game costs one request per tick
+pub struct Schedule {
+    next_due: [Instant; 4],
+}
+
+impl Default for Schedule {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Schedule {
+    /// Everything is due straight away
+    pub fn new() -> Self {
+        Self {
+            next_due: [Instant::now(); 4],
+        }
+    }
+
+    /// The endpoint that's due first, and when
+    pub fn next(&self) -> (Endpoint, Instant) {
+        Endpoint::ALL
+            .into_iter()
+            .zip(self.next_due)
+            .min_by_key(|(_, due)| *due)
+            .expect("there's always an endpoint")
+    }
+
+    /// Marks `endpoint` as just polled, it's due again after its interval
+    pub fn polled(&mut self, endpoint: Endpoint, config: &PollConfig) {
+        let index = Endpoint::ALL
+            .iter()
+            .position(|e| *e == endpoint)
+            .expect("every endpoint is in ALL");
+        self.next_due[index] = Instant::now() + config.interval(endpoint);
+    }
+}
+
+/// Doubling delays (with jitter) between tries while there's no game
+pub struct Backoff {
+    min: Duration,
+    max: Duration,
+    attempts: u32,
+}
+
+impl Backoff {
+    /// Starts at backoff_min
+    pub fn new(config: &PollConfig) -> Self {
+        Self {
+            min: config.backoff_min,
+            max: config.backoff_max,
+            attempts: 0,
+        }
+    }
+
+    /// How long to wait before trying again, somewhere between half and all of the doubled delay
+    pub fn next_delay(&mut self) -> Duration {
+        let delay = self
+            .min
+            .saturating_mul(2u32.saturating_pow(self.attempts))
+            .min(self.max);
+        self.attempts = self.attempts.saturating_add(1);
+        delay.mul_f64(0.5 + jitter() * 0.5)
+    }
+
+    /// Back to backoff_min, once a try goes through
+    pub fn reset(&mut self) {
+        self.attempts = 0;
+    }
+}
+
+//0..1, doesn't need to be good randomness, just enough that retries don't line up
+fn jitter() -> f64 {
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.subsec_nanos())
+        .unwrap_or_default();
+    nanos as f64 / 1_000_000_000.0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn the_soonest_endpoint_goes_next() {
+        let config = PollConfig::default();
+        let mut schedule = Schedule::new();
+        for endpoint in [
+            Endpoint::ActivePlayer,
+            Endpoint::PlayerList,
+            Endpoint::GameStats,
+        ] {
+            schedule.polled(endpoint, &config);
+        }
+        assert_eq!(schedule.next().0, Endpoint::Events);
+
+        schedule.polled(Endpoint::Events, &config);
+        // 250ms beats everything else
+        let (endpoint, due) = schedule.next();
+        assert_eq!(endpoint, Endpoint::ActivePlayer);
+        assert!(due > Instant::now());
+    }
+
+    #[test]
+    fn backoff_doubles_up_to_max_and_resets() {
+        let config = PollConfig {
+            backoff_min: Duration::from_secs(1),
+            backoff_max: Duration::from_secs(4),
+            ..PollConfig::default()
+        };
+        let mut backoff = Backoff::new(&config);
+        for secs in [1, 2, 4, 4, 4] {
+            let full = Duration::from_secs(secs);
+            let delay = backoff.next_delay();
+            assert!(
+                delay >= full / 2 && delay <= full,
+                "{:?} for {:?}",
+                delay,
+                full
+            );
+        }
+
+        backoff.reset();
+        assert!(backoff.next_delay() <= Duration::from_secs(1));
+    }
+
+    #[test]
+    fn overrides_are_checked() {
+        let mut config = PollConfig::default();
+        config
+            .apply_overrides("activeplayer=500, backoff_max=60000")
+            .unwrap();
+        assert_eq!(config.active_player, Duration::from_millis(500));
+        assert_eq!(config.backoff_max, Duration::from_secs(60));
+
+        assert!(config.apply_overrides("events=0").is_err());
+        assert!(config.apply_overrides("backoff_min=90000").is_err());
+        assert!(config.apply_overrides("nope=5").is_err());
+    }
+}
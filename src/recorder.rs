@@ -0,0 +1,258 @@
+use anyhow::{Context, Result, anyhow};
+use flate2::Compression;
+use flate2::read::MultiGzDecoder;
+use flate2::write::GzEncoder;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::fs::{File, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+use tokio::sync::mpsc;
+
+/*
+--record <path> writes a snapshot of the game into a gzipped ndjson file every /gamestats poll,
+one json object per line:
+    {"type":"header","app_version":"0.1.0","game_mode":"CLASSIC","champion":"Ahri","riot_id":"...","started_at":...}
+    {"type":"snapshot","timestamp":1718000000000,"data":{ ...allgamedata... }}
+The fetcher puts `data` together from the latest response of each endpoint, shaped like /allgamedata.
+Every game starts with a header. The file is only ever appended to, so recording to the same path
+again adds another gzip member (gzip tools and flate2's MultiGzDecoder read them as one stream).
+A file whose last member got cut off (the overlay was killed mid-write) can't be added to, readers
+would stop at the cut, so the recording goes to "<name>-1.ndjson.gz" (or -2, ...) instead.
+Timestamps are unix milliseconds.
+Writing happens on a blocking thread of its own (spawn_blocking), the fetcher only hands lines over.
+*/
+
+/// A line of a recording
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum RecordLine {
+    /// Starts every game in the file
+    Header(SessionHeader),
+    /// The game at one /gamestats poll, shaped like /allgamedata
+    Snapshot {
+        /// When it was received, unix milliseconds
+        timestamp: u64,
+        /// The latest response of each endpoint, unparsed
+        data: Value,
+    },
+}
+
+/// Who and what was played, written before the game's first snapshot
+#[derive(Serialize, Deserialize, Debug)]
+pub struct SessionHeader {
+    /// Version of the overlay that made the recording
+    pub app_version: String,
+    /// "CLASSIC", "ARAM", ...
+    pub game_mode: Option<String>,
+    /// The active player's champion
+    pub champion: Option<String>,
+    /// The active player's Riot ID
+    pub riot_id: Option<String>,
+    /// Unix milliseconds, the same as the first snapshot's timestamp
+    pub started_at: u64,
+}
+
+/// Writes a recording in the background (see the top of the file)
+pub struct Recorder {
+    lines: mpsc::UnboundedSender<RecordLine>,
+    needs_header: bool,
+}
+
+impl Recorder {
+    /// Opens (or appends to) the recording, has to be called from inside a tokio runtime
+    pub fn create(path: &Path) -> Result<Self> {
+        let path = &appendable_path(path)?;
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .with_context(|| format!("Failed to open recording {}", path.display()))?;
+        log::info!("Recording game data to {}", path.display());
+
+        let (lines, mut receiver) = mpsc::unbounded_channel();
+        let mut encoder = GzEncoder::new(file, Compression::default());
+        tokio::task::spawn_blocking(move || {
+            while let Some(line) = receiver.blocking_recv() {
+                if let Err(e) = write_line(&mut encoder, &line) {
+                    log::error!("Failed to record game data, recording stopped: {:#}", e);
+                    return;
+                }
+            }
+        });
+
+        Ok(Recorder {
+            lines,
+            needs_header: true,
+        })
+    }
+
+    /// The next snapshot belongs to a different game and gets a fresh header
+    pub fn start_session(&mut self) {
+        self.needs_header = true;
+    }
+
+    /// Queues a snapshot for writing, Err once the writer has given up
+    pub fn record(&mut self, data: &Value) -> Result<()> {
+        let timestamp = unix_millis();
+        if self.needs_header {
+            self.send(RecordLine::Header(SessionHeader::from_snapshot(
+                data, timestamp,
+            )))?;
+            self.needs_header = false;
+        }
+        self.send(RecordLine::Snapshot {
+            timestamp,
+            data: data.clone(),
+        })
+    }
+
+    fn send(&self, line: RecordLine) -> Result<()> {
+        self.lines
+            .send(line)
+            .map_err(|_| anyhow!("the recording stopped"))
+    }
+}
+
+// The path itself unless it holds a recording that was cut off, then the first suffixed one that's
+// free or whole
+fn appendable_path(path: &Path) -> Result<PathBuf> {
+    let mut candidate = path.to_path_buf();
+    for n in 1.. {
+        if reads_to_the_end(&candidate)
+            .with_context(|| format!("Failed to read recording {}", candidate.display()))?
+        {
+            if n > 1 {
+                log::warn!(
+                    "{} ends in a cut off game, recording to {} instead",
+                    path.display(),
+                    candidate.display()
+                );
+            }
+            return Ok(candidate);
+        }
+        candidate = with_suffix(path, n);
+    }
+    unreachable!()
+}
+
+// Whether new members can go after what's in the file, Ok(true) if there's no file yet
+fn reads_to_the_end(path: &Path) -> io::Result<bool> {
+    let file = match File::open(path) {
+        Ok(file) => file,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(true),
+        Err(e) => return Err(e),
+    };
+    if file.metadata()?.len() == 0 {
+        return Ok(true);
+    }
+    Ok(io::copy(&mut MultiGzDecoder::new(file), &mut io::sink()).is_ok())
+}
+
+// "game.ndjson.gz" -> "game-1.ndjson.gz"
+fn with_suffix(path: &Path, n: u32) -> PathBuf {
+    let name = path.file_name().unwrap_or_default().to_string_lossy();
+    let (stem, extensions) = match name.find('.') {
+        Some(dot) if dot > 0 => name.split_at(dot),
+        _ => (name.as_ref(), ""),
+    };
+    path.with_file_name(format!("{stem}-{n}{extensions}"))
+}
+
+fn write_line(encoder: &mut GzEncoder<File>, line: &RecordLine) -> Result<()> {
+    // One write for the whole line, the encoder is slow with serde's many small ones
+    let mut text = serde_json::to_vec(line)?;
+    text.push(b'\n');
+    encoder.write_all(&text)?;
+    // Sync flush so everything up to here can be read back even if we never close cleanly
+    encoder.flush()?;
+    Ok(())
+}
+
+impl SessionHeader {
+    //Read straight from the json so a header still gets written when the snapshot doesn't parse
+    fn from_snapshot(data: &Value, started_at: u64) -> Self {
+        let text = |pointer: &str| data.pointer(pointer).and_then(Value::as_str);
+        let riot_id = text("/activePlayer/riotId");
+        let champion = data
+            .pointer("/allPlayers")
+            .and_then(Value::as_array)
+            .and_then(|players| {
+                players.iter().find(|p| {
+                    riot_id.is_some() && p.get("riotId").and_then(Value::as_str) == riot_id
+                })
+            })
+            .and_then(|p| p.get("championName"))
+            .and_then(Value::as_str);
+
+        SessionHeader {
+            app_version: env!("CARGO_PKG_VERSION").to_string(),
+            game_mode: text("/gameData/gameMode").map(str::to_string),
+            champion: champion.map(str::to_string),
+            riot_id: riot_id.map(str::to_string),
+            started_at,
+        }
+    }
+}
+
+fn unix_millis() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    fn recording(games: usize) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        for game in 0..games {
+            let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+            writeln!(encoder, "{{\"game\":{game}}}").unwrap();
+            bytes.extend(encoder.finish().unwrap());
+        }
+        bytes
+    }
+
+    #[test]
+    fn whole_recordings_are_appended_to() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("games.ndjson.gz");
+        assert_eq!(appendable_path(&path).unwrap(), path);
+
+        fs::write(&path, recording(2)).unwrap();
+        assert_eq!(appendable_path(&path).unwrap(), path);
+    }
+
+    #[test]
+    fn a_cut_off_recording_is_left_alone() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("games.ndjson.gz");
+        let whole = recording(2);
+        fs::write(&path, &whole[..whole.len() - 10]).unwrap();
+        assert_eq!(
+            appendable_path(&path).unwrap(),
+            dir.path().join("games-1.ndjson.gz")
+        );
+
+        fs::write(dir.path().join("games-1.ndjson.gz"), &whole[..20]).unwrap();
+        assert_eq!(
+            appendable_path(&path).unwrap(),
+            dir.path().join("games-2.ndjson.gz")
+        );
+    }
+
+    #[test]
+    fn suffixes_go_before_the_extensions() {
+        assert_eq!(
+            with_suffix(Path::new("dir/games.ndjson.gz"), 3),
+            Path::new("dir/games-3.ndjson.gz")
+        );
+        assert_eq!(with_suffix(Path::new("games"), 1), Path::new("games-1"));
+        assert_eq!(with_suffix(Path::new(".games"), 1), Path::new(".games-1"));
+    }
+}
@@ -0,0 +1,438 @@
+use anyhow::{Context, Result, bail};
+use flate2::read::MultiGzDecoder;
+use league_overlay::event_cursor::EventCursor;
+use league_overlay::fetcher::send_snapshot;
+use league_overlay::recorder::RecordLine;
+use league_overlay::schema::{self, SchemaReport};
+use league_overlay::{GameInfo, GameUpdate};
+use serde_json::Value;
+use std::fs::File;
+use std::io::{BufRead, BufReader, Read};
+use std::path::Path;
+use std::str::FromStr;
+use tokio::sync::mpsc;
+use tokio::time::{Duration, Instant};
+
+/*
+Plays a recording (see recorder.rs, gzipped or not) back into the overlay like game_data_fetcher would,
+at the pace the snapshots were captured. Every header starts a new session (a game), each one is a
+timeline of its own: the time League was closed in between isn't waited out, and seeking stays
+inside the game that's playing. Speed, pause and seek are controlled with ReplayCommands, which come
+from the replay hotkeys (see hotkey.rs) or are typed into the terminal:
+    pause         pause/unpause
+    speed 4       0.5x to 16x
+    faster        double the speed (slower halves it)
+    seek 12:30    jump to the first snapshot at or after that game time
+    seek +30      30 seconds on (seek -30 goes back)
+*/
+
+pub const MIN_SPEED: f64 = 0.5;
+pub const MAX_SPEED: f64 = 16.0;
+//What the replay hotkeys skip
+pub const SEEK_STEP: f64 = 30.0;
+//The pause between one recorded game and the next
+const SESSION_GAP: Duration = Duration::from_secs(2);
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ReplayCommand {
+    TogglePause,
+    SetSpeed(f64),
+    Faster,
+    Slower,
+    //Game time in seconds
+    Seek(f64),
+    //Seconds from the current game time, back if negative
+    SeekBy(f64),
+}
+
+impl FromStr for ReplayCommand {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let mut parts = s.split_whitespace();
+        let command = match (parts.next(), parts.next()) {
+            (Some("pause"), None) => ReplayCommand::TogglePause,
+            (Some("speed"), Some(speed)) => {
+                ReplayCommand::SetSpeed(speed.trim_end_matches('x').parse()?)
+            }
+            (Some("faster"), None) => ReplayCommand::Faster,
+            (Some("slower"), None) => ReplayCommand::Slower,
+            (Some("seek"), Some(time)) => match time.strip_prefix('+') {
+                Some(forward) => ReplayCommand::SeekBy(parse_game_time(forward)?),
+                None => match time.strip_prefix('-') {
+                    Some(back) => ReplayCommand::SeekBy(-parse_game_time(back)?),
+                    None => ReplayCommand::Seek(parse_game_time(time)?),
+                },
+            },
+            _ => bail!(
+                "Unknown replay command {:?} (pause | speed <x> | faster | slower | seek <m:ss> | seek +<s> | seek -<s>)",
+                s
+            ),
+        };
+        Ok(command)
+    }
+}
+
+struct Snapshot {
+    //Which game in the recording, counting headers
+    session: usize,
+    timestamp: u64,
+    game_time: Option<f64>,
+    data: Value,
+}
+
+pub async fn replay_fetcher(
+    path: &Path,
+    sender: mpsc::UnboundedSender<GameUpdate>,
+    mut commands: mpsc::UnboundedReceiver<ReplayCommand>,
+    speed: f64,
+) -> Result<()> {
+    let snapshots = load_snapshots(path)?;
+    if snapshots.is_empty() {
+        bail!("{} has no snapshots in it", path.display());
+    }
+    println!(
+        "Replaying {} snapshots from {}",
+        snapshots.len(),
+        path.display()
+    );
+
+    let mut cursor = EventCursor::new();
+    let mut report = SchemaReport::default();
+    let mut speed = clamp_speed(speed);
+    let mut paused = false;
+    let mut commands_closed = false;
+    let mut index = 0;
+
+    loop {
+        let snapshot = &snapshots[index];
+        // A new game starts a new event log
+        if index > 0 && snapshots[index - 1].session != snapshot.session {
+            println!("Replaying game {}", snapshot.session + 1);
+            if cursor.reset() && sender.send(GameUpdate::EventsReset).is_err() {
+                break;
+            }
+        }
+        match schema::parse_lenient::<GameInfo>("recording", snapshot.data.clone(), &mut report) {
+            Ok(game_info) => {
+                if send_snapshot(&sender, &mut cursor, game_info).is_err() {
+                    println!("Failed to send game data - receiver likely dropped");
+                    break;
+                }
+            }
+            Err(e) => println!("Failed to parse snapshot {}: {}", index, e),
+        }
+
+        // Wall clock gap to the next snapshot, None at the end of the recording
+        let gap = snapshots.get(index + 1).map(|next| {
+            if next.session == snapshot.session {
+                Duration::from_millis(next.timestamp.saturating_sub(snapshot.timestamp))
+            } else {
+                SESSION_GAP.mul_f64(speed)
+            }
+        });
+        if gap.is_none() {
+            if commands_closed {
+                break;
+            }
+            println!("Replay finished, seek to keep watching");
+        }
+        let mut deadline = gap.map(|gap| Instant::now() + gap.div_f64(speed));
+        let mut paused_left = if paused {
+            gap.map(|gap| gap.div_f64(speed))
+        } else {
+            None
+        };
+
+        // Wait for the next snapshot, or a command that changes what the next snapshot is
+        let next_index = loop {
+            let sleep = async {
+                match deadline {
+                    Some(deadline) if !paused => tokio::time::sleep_until(deadline).await,
+                    _ => std::future::pending().await,
+                }
+            };
+
+            tokio::select! {
+                _ = sleep => break index + 1,
+                command = commands.recv(), if !commands_closed => match command {
+                    None => {
+                        // Nobody can send commands anymore, just play through to the end
+                        commands_closed = true;
+                        if paused {
+                            paused = false;
+                            deadline = paused_left.take().map(|left| Instant::now() + left);
+                        }
+                        if deadline.is_none() {
+                            return Ok(());
+                        }
+                    }
+                    Some(ReplayCommand::TogglePause) => {
+                        paused = !paused;
+                        println!("Replay {}", if paused { "paused" } else { "resumed" });
+                        if paused {
+                            paused_left = deadline.map(|d| d.saturating_duration_since(Instant::now()));
+                        } else if let Some(left) = paused_left.take() {
+                            deadline = Some(Instant::now() + left);
+                        }
+                    }
+                    Some(command @ (ReplayCommand::SetSpeed(_) | ReplayCommand::Faster | ReplayCommand::Slower)) => {
+                        let new_speed = clamp_speed(match command {
+                            ReplayCommand::Faster => speed * 2.0,
+                            ReplayCommand::Slower => speed / 2.0,
+                            ReplayCommand::SetSpeed(new_speed) => new_speed,
+                            _ => speed,
+                        });
+                        // Stretch whatever is left of the current gap to the new speed
+                        if let Some(d) = deadline {
+                            let left = d.saturating_duration_since(Instant::now());
+                            deadline = Some(Instant::now() + left.mul_f64(speed / new_speed));
+                        }
+                        speed = new_speed;
+                        println!("Replay speed {}x", speed);
+                    }
+                    Some(ReplayCommand::Seek(game_time)) => {
+                        let target = seek(&snapshots, index, game_time);
+                        println!("Seeking to snapshot {}", target);
+                        break target;
+                    }
+                    Some(ReplayCommand::SeekBy(seconds)) => {
+                        let Some(game_time) = snapshot.game_time else {
+                            continue;
+                        };
+                        let target = seek(&snapshots, index, game_time + seconds);
+                        println!("Seeking to snapshot {}", target);
+                        break target;
+                    }
+                },
+            }
+        };
+        index = next_index;
+    }
+
+    Ok(())
+}
+
+// The first snapshot at or after game_time in the session `index` is in, its last one if none is
+fn seek(snapshots: &[Snapshot], index: usize, game_time: f64) -> usize {
+    let session = snapshots[index].session;
+    let start = snapshots[..index]
+        .iter()
+        .rposition(|s| s.session != session)
+        .map_or(0, |before| before + 1);
+    let end = snapshots[index..]
+        .iter()
+        .position(|s| s.session != session)
+        .map_or(snapshots.len(), |after| index + after);
+    snapshots[start..end]
+        .iter()
+        .position(|s| s.game_time.is_some_and(|t| t >= game_time))
+        .map_or(end - 1, |found| start + found)
+}
+
+fn load_snapshots(path: &Path) -> Result<Vec<Snapshot>> {
+    let mut file =
+        File::open(path).with_context(|| format!("Failed to open {}", path.display()))?;
+    let mut magic = [0u8; 2];
+    let is_gzip = file.read(&mut magic)? == 2 && magic == [0x1f, 0x8b];
+    drop(file);
+
+    let file = File::open(path)?;
+    let reader: Box<dyn BufRead> = if is_gzip {
+        Box::new(BufReader::new(MultiGzDecoder::new(file)))
+    } else {
+        Box::new(BufReader::new(file))
+    };
+
+    let mut snapshots: Vec<Snapshot> = Vec::new();
+    //A header before any snapshot is the first game's, not a second one
+    let mut session = 0;
+    let mut lines = reader.lines().enumerate().peekable();
+    while let Some((number, line)) = lines.next() {
+        let line = match line {
+            Ok(line) => line,
+            // A recording that was cut off mid write still has everything before the cut
+            Err(e) if !snapshots.is_empty() => {
+                println!(
+                    "Stopped reading {} at line {}: {}",
+                    path.display(),
+                    number + 1,
+                    e
+                );
+                break;
+            }
+            Err(e) => return Err(e.into()),
+        };
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        let record: RecordLine = match serde_json::from_str(&line) {
+            Ok(record) => record,
+            // The same goes for a last line that was only partly written
+            Err(e) if !snapshots.is_empty() && !matches!(lines.peek(), Some((_, Ok(_)))) => {
+                println!(
+                    "Stopped reading {} at line {}: {}",
+                    path.display(),
+                    number + 1,
+                    e
+                );
+                break;
+            }
+            Err(e) => {
+                return Err(e).with_context(|| {
+                    format!(
+                        "{} line {} is not a recording line",
+                        path.display(),
+                        number + 1
+                    )
+                });
+            }
+        };
+        match record {
+            RecordLine::Header(header) => {
+                if !snapshots.is_empty() {
+                    session += 1;
+                }
+                println!(
+                    "Recorded game {}: {} {} ({})",
+                    session + 1,
+                    header.game_mode.as_deref().unwrap_or("?"),
+                    header.champion.as_deref().unwrap_or("?"),
+                    header.riot_id.as_deref().unwrap_or("?"),
+                )
+            }
+            RecordLine::Snapshot { timestamp, data } => snapshots.push(Snapshot {
+                session,
+                timestamp,
+                game_time: data.pointer("/gameData/gameTime").and_then(Value::as_f64),
+                data,
+            }),
+        }
+    }
+
+    Ok(snapshots)
+}
+
+fn clamp_speed(speed: f64) -> f64 {
+    if speed.is_nan() {
+        return 1.0;
+    }
+    speed.clamp(MIN_SPEED, MAX_SPEED)
+}
+
+/// "m:ss" or plain seconds
+pub fn parse_game_time(text: &str) -> Result<f64> {
+    match text.split_once(':') {
+        Some((minutes, seconds)) => {
+            Ok(minutes.parse::<u32>()? as f64 * 60.0 + seconds.parse::<f64>()?)
+        }
+        None => Ok(text.parse()?),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use flate2::Compression;
+    use flate2::write::GzEncoder;
+    use std::io::Write;
+
+    const RECORDING: &str = concat!(
+        r#"{"type":"header","app_version":"0.1.0","game_mode":"ARAM","champion":null,"riot_id":null,"started_at":0}"#,
+        "\n",
+        r#"{"type":"snapshot","timestamp":0,"data":{"gameData":{"gameTime":1.0}}}"#,
+        "\n",
+        r#"{"type":"snapshot","timestamp":1000,"data":{"gameData":{"gameTime":2.0}}}"#,
+        "\n",
+    );
+
+    fn load(bytes: &[u8]) -> Result<Vec<Snapshot>> {
+        let file = tempfile::NamedTempFile::new().unwrap();
+        std::fs::write(file.path(), bytes).unwrap();
+        load_snapshots(file.path())
+    }
+
+    fn snapshot(session: usize, game_time: f64) -> Snapshot {
+        Snapshot {
+            session,
+            timestamp: 0,
+            game_time: Some(game_time),
+            data: Value::Null,
+        }
+    }
+
+    #[test]
+    fn a_recording_cut_off_mid_line_keeps_what_was_read() {
+        let cut = &RECORDING[..RECORDING.len() - 20];
+        let snapshots = load(cut.as_bytes()).unwrap();
+        assert_eq!(snapshots.len(), 1);
+        assert_eq!(snapshots[0].game_time, Some(1.0));
+
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(cut.as_bytes()).unwrap();
+        let gzipped = encoder.finish().unwrap();
+        assert_eq!(load(&gzipped).unwrap().len(), 1);
+        // Without the gzip trailer it's the decoder that stops, after the whole lines
+        assert_eq!(load(&gzipped[..gzipped.len() - 8]).unwrap().len(), 1);
+    }
+
+    #[test]
+    fn a_broken_line_before_the_end_is_an_error() {
+        let broken = RECORDING.replacen(r#""timestamp":0"#, r#""timestamp":"#, 1);
+        assert!(load(broken.as_bytes()).is_err());
+        assert_eq!(load(RECORDING.as_bytes()).unwrap().len(), 2);
+    }
+
+    #[test]
+    fn game_times_read_as_minutes_and_seconds_or_plain_seconds() {
+        assert_eq!(parse_game_time("12:30").unwrap(), 750.0);
+        assert_eq!(parse_game_time("0:05.5").unwrap(), 5.5);
+        assert_eq!(parse_game_time("90").unwrap(), 90.0);
+        assert!(parse_game_time("1:xx").is_err());
+        assert!(parse_game_time("-1:00").is_err());
+    }
+
+    #[test]
+    fn commands_parse() {
+        assert_eq!(
+            "pause".parse::<ReplayCommand>().unwrap(),
+            ReplayCommand::TogglePause
+        );
+        assert_eq!(
+            "speed 4x".parse::<ReplayCommand>().unwrap(),
+            ReplayCommand::SetSpeed(4.0)
+        );
+        assert_eq!(
+            "seek 1:00".parse::<ReplayCommand>().unwrap(),
+            ReplayCommand::Seek(60.0)
+        );
+        assert_eq!(
+            "seek -30".parse::<ReplayCommand>().unwrap(),
+            ReplayCommand::SeekBy(-30.0)
+        );
+        assert_eq!(
+            "seek +0:30".parse::<ReplayCommand>().unwrap(),
+            ReplayCommand::SeekBy(30.0)
+        );
+        assert!("rewind".parse::<ReplayCommand>().is_err());
+    }
+
+    #[test]
+    fn seeking_stays_in_the_session_playing() {
+        let snapshots = [
+            snapshot(0, 10.0),
+            snapshot(0, 20.0),
+            snapshot(0, 30.0),
+            snapshot(1, 5.0),
+            snapshot(1, 15.0),
+            snapshot(1, 25.0),
+        ];
+        // The second game's 15s, not the first game's 20s
+        assert_eq!(seek(&snapshots, 5, 12.0), 4);
+        assert_eq!(seek(&snapshots, 0, 12.0), 1);
+        // Past the end stops at the session's last snapshot, before the start at its first
+        assert_eq!(seek(&snapshots, 1, 99.0), 2);
+        assert_eq!(seek(&snapshots, 4, -10.0), 3);
+    }
+}
@@ -0,0 +1,293 @@
+use crate::live_client::LiveClientError;
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+use serde_json::Value;
+use serde_path_to_error::Segment;
+use std::collections::BTreeMap;
+use std::fmt;
+
+/*
+Parsing that keeps going when Riot changes the payload.
+Every model defaults field by field (#[serde(default)]), so a missing field just leaves that field
+empty. A field that's there but can't be read (a number turned into a string, say) is cut out of the
+json and the parse tried again, so it gets its default too. Once parsed, the model is written back
+out and compared with what came in to find the fields we don't know about and the ones that were missing.
+Every odd field is logged the first time it shows up and kept in a SchemaReport for later.
+*/
+
+//Fields cut out before giving up on a payload
+const MAX_REPAIRS: usize = 32;
+
+/// What was wrong with a field
+#[derive(Debug, Clone, PartialEq)]
+pub enum FieldProblem {
+    /// In the payload but not in our models, ignored
+    Unknown,
+    /// In our models but not in the payload, left at its default
+    Missing,
+    /// Couldn't be read, left at its default
+    Invalid(String),
+    /// A list entry that couldn't be read, left out of the list
+    Dropped(String),
+}
+
+impl fmt::Display for FieldProblem {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            FieldProblem::Unknown => write!(f, "unknown, ignored"),
+            FieldProblem::Missing => write!(f, "missing, left at its default"),
+            FieldProblem::Invalid(message) => {
+                write!(f, "invalid ({}), left at its default", message)
+            }
+            FieldProblem::Dropped(message) => write!(f, "invalid ({}), left out", message),
+        }
+    }
+}
+
+/// Every field that didn't match the models, by endpoint and path (array indices left out)
+#[derive(Debug, Clone, Default)]
+pub struct SchemaReport {
+    problems: BTreeMap<(String, String), FieldProblem>,
+}
+
+impl SchemaReport {
+    /// Whether every payload so far fit the models
+    pub fn is_empty(&self) -> bool {
+        self.problems.is_empty()
+    }
+
+    /// (endpoint, path, problem) for every field, sorted by endpoint and path
+    pub fn problems(&self) -> impl Iterator<Item = (&str, &str, &FieldProblem)> {
+        self.problems
+            .iter()
+            .map(|((endpoint, path), problem)| (endpoint.as_str(), path.as_str(), problem))
+    }
+
+    // Logs the problem the first time this field has one
+    fn record(&mut self, endpoint: &str, path: String, problem: FieldProblem) {
+        let key = (endpoint.to_string(), path);
+        if self.problems.contains_key(&key) {
+            return;
+        }
+        log::warn!("Payload change in {} at {}: {}", key.0, key.1, problem);
+        self.problems.insert(key, problem);
+    }
+}
+
+impl fmt::Display for SchemaReport {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Live Client fields that don't match the models:")?;
+        for (endpoint, path, problem) in self.problems() {
+            write!(f, "\n    {} {}: {}", endpoint, path, problem)?;
+        }
+        Ok(())
+    }
+}
+
+/// Parses `value` field by field, noting anything that didn't fit in `report`.
+/// Only fails if the payload isn't the right shape at all
+pub fn parse_lenient<T>(
+    endpoint: &str,
+    mut value: Value,
+    report: &mut SchemaReport,
+) -> Result<T, LiveClientError>
+where
+    T: DeserializeOwned + Serialize,
+{
+    let mut repairs = 0;
+    let parsed = loop {
+        let error = match serde_path_to_error::deserialize::<_, T>(&value) {
+            Ok(parsed) => break parsed,
+            Err(error) => error,
+        };
+        let segments: Vec<Segment> = error.path().iter().cloned().collect();
+        if repairs == MAX_REPAIRS || !remove(&mut value, &segments) {
+            return Err(LiveClientError::Deserialize {
+                endpoint: endpoint.to_string(),
+                path: error.path().to_string(),
+                message: error.inner().to_string(),
+            });
+        }
+        let message = error.inner().to_string();
+        let problem = match segments.last() {
+            Some(Segment::Seq { .. }) => FieldProblem::Dropped(message),
+            _ => FieldProblem::Invalid(message),
+        };
+        report.record(endpoint, path_of(&segments), problem);
+        repairs += 1;
+    };
+
+    // Writing the model back out can't fail, it came from json in the first place
+    let written = serde_json::to_value(&parsed).unwrap_or(Value::Null);
+    // serde happily fills a struct from a list, which is never what Riot meant
+    if !written.is_null() && shape(&value) != shape(&written) {
+        return Err(LiveClientError::Deserialize {
+            endpoint: endpoint.to_string(),
+            path: ".".to_string(),
+            message: format!("expected {}, got {}", shape(&written), shape(&value)),
+        });
+    }
+    compare(endpoint, &value, &written, "", report);
+    Ok(parsed)
+}
+
+fn shape(value: &Value) -> &'static str {
+    match value {
+        Value::Object(_) => "an object",
+        Value::Array(_) => "a list",
+        _ => "a value",
+    }
+}
+
+// Cuts whatever `segments` points at out of `value`, false if it can't be found
+fn remove(value: &mut Value, segments: &[Segment]) -> bool {
+    let Some((last, parents)) = segments.split_last() else {
+        return false;
+    };
+    let mut parent = value;
+    for segment in parents {
+        let child = match segment {
+            Segment::Seq { index } => parent.get_mut(*index),
+            Segment::Map { key } => parent.get_mut(key),
+            Segment::Enum { .. } | Segment::Unknown => None,
+        };
+        match child {
+            Some(child) => parent = child,
+            None => return false,
+        }
+    }
+    match (last, parent) {
+        (Segment::Map { key }, Value::Object(fields)) => fields.remove(key).is_some(),
+        (Segment::Seq { index }, Value::Array(items)) if *index < items.len() => {
+            items.remove(*index);
+            true
+        }
+        _ => false,
+    }
+}
+
+fn path_of(segments: &[Segment]) -> String {
+    segments
+        .iter()
+        .fold(String::new(), |path, segment| match segment {
+            Segment::Seq { .. } => path + "[]",
+            Segment::Map { key } => join(&path, key),
+            Segment::Enum { variant } => join(&path, variant),
+            Segment::Unknown => join(&path, "?"),
+        })
+}
+
+fn join(path: &str, key: &str) -> String {
+    if path.is_empty() {
+        key.to_string()
+    } else {
+        format!("{}.{}", path, key)
+    }
+}
+
+// Walks the payload and our copy of it side by side
+fn compare(endpoint: &str, raw: &Value, written: &Value, path: &str, report: &mut SchemaReport) {
+    match (raw, written) {
+        (Value::Object(raw), Value::Object(written)) => {
+            for (key, raw_value) in raw {
+                let field = join(path, key);
+                match written.get(key) {
+                    Some(written_value) => {
+                        compare(endpoint, raw_value, written_value, &field, report)
+                    }
+                    None => report.record(endpoint, field, FieldProblem::Unknown),
+                }
+            }
+            // A None that wasn't sent is just an optional field that isn't there
+            for (key, written_value) in written {
+                if !raw.contains_key(key) && !written_value.is_null() {
+                    report.record(endpoint, join(path, key), FieldProblem::Missing);
+                }
+            }
+        }
+        (Value::Array(raw), Value::Array(written)) => {
+            let items = format!("{}[]", path);
+            for (raw_item, written_item) in raw.iter().zip(written) {
+                compare(endpoint, raw_item, written_item, &items, report);
+            }
+        }
+        (raw, written) if !written.is_null() && shape(raw) != shape(written) => {
+            let message = format!("expected {}, got {}", shape(written), shape(raw));
+            report.record(endpoint, path.to_string(), FieldProblem::Invalid(message));
+        }
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde::Deserialize;
+    use serde_json::json;
+
+    #[derive(Debug, Default, PartialEq, Serialize, Deserialize)]
+    #[serde(default)]
+    struct Model {
+        name: String,
+        level: u32,
+        items: Vec<u32>,
+    }
+
+    #[test]
+    fn bad_fields_are_cut_out_and_reported() {
+        let mut report = SchemaReport::default();
+        let payload = json!({ "level": "7", "items": [1, "two", 3], "shiny": true });
+        let parsed: Model = parse_lenient("test", payload, &mut report).unwrap();
+        assert_eq!(
+            parsed,
+            Model {
+                name: String::new(),
+                level: 0,
+                items: vec![1, 3],
+            }
+        );
+        let problems: Vec<(&str, &FieldProblem)> = report
+            .problems()
+            .map(|(_, path, problem)| (path, problem))
+            .collect();
+        assert!(matches!(problems[0], ("items[]", FieldProblem::Dropped(_))));
+        assert!(matches!(problems[1], ("level", FieldProblem::Invalid(_))));
+        assert_eq!(
+            problems[2..],
+            [
+                ("name", &FieldProblem::Missing),
+                ("shiny", &FieldProblem::Unknown)
+            ]
+        );
+    }
+
+    #[test]
+    fn a_payload_that_fits_leaves_the_report_empty() {
+        let mut report = SchemaReport::default();
+        let payload = json!({ "name": "Ahri", "level": 7, "items": [] });
+        let _: Model = parse_lenient("test", payload, &mut report).unwrap();
+        assert!(report.is_empty());
+    }
+
+    #[test]
+    fn the_wrong_shape_is_an_error() {
+        let mut report = SchemaReport::default();
+        let list = json!(["Ahri", 7, []]);
+        assert!(parse_lenient::<Model>("test", list, &mut report).is_err());
+        assert!(parse_lenient::<Model>("test", json!("Ahri"), &mut report).is_err());
+    }
+
+    #[test]
+    fn repairs_stop_after_the_limit() {
+        let mut report = SchemaReport::default();
+        let items = vec![json!("bad"); MAX_REPAIRS];
+        let payload = json!({ "items": items });
+        let parsed: Model = parse_lenient("test", payload, &mut report).unwrap();
+        assert!(parsed.items.is_empty());
+
+        let items = vec![json!("bad"); MAX_REPAIRS + 1];
+        let payload = json!({ "items": items });
+        let error = parse_lenient::<Model>("test", payload, &mut report).unwrap_err();
+        assert!(matches!(error, LiveClientError::Deserialize { .. }));
+    }
+}
@@ -0,0 +1,469 @@
+use crate::config::{self, ColorRule, Config, Font, HexColor, PanelConfig, RowConfig};
+use crate::hotkey::HotkeyAction;
+use crate::panels::PanelKind;
+use crate::placement::Anchor;
+use eframe::egui;
+use egui::{Color32, ComboBox, DragValue, RichText, Slider};
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+
+/*
+The settings menu, opened over the overlay with the settings hotkey (Ctrl+Alt+F9 by default).
+It edits a copy of what's in the config file, and saving writes that copy back. The file watcher
+(see config.rs) then reloads it like any other edit, so the overlay only has one way of changing settings.
+Command line flags still win over whatever gets saved here.
+*/
+
+/// Room the menu needs, the overlay grows to at least this while it's open
+pub const MENU_SIZE: egui::Vec2 = egui::vec2(560.0, 620.0);
+
+pub struct SettingsMenu {
+    draft: Config,
+    path: Option<PathBuf>,
+    //How the last save went, shown next to the buttons
+    message: Option<(bool, String)>,
+}
+
+enum RowEdit {
+    Up(usize),
+    Down(usize),
+    OtherColumn(usize),
+    Remove(usize),
+}
+
+impl SettingsMenu {
+    /// Starts from the file so command line overrides don't get saved into it
+    pub fn open(current: &Config, path: Option<PathBuf>) -> Self {
+        let draft = path
+            .as_deref()
+            .and_then(|path| config::read(path).ok())
+            .unwrap_or_else(|| current.clone());
+        Self {
+            draft,
+            path,
+            message: None,
+        }
+    }
+
+    /// Returns true once the menu wants to close
+    pub fn show(&mut self, ui: &mut egui::Ui) -> bool {
+        let mut close = false;
+        ui.vertical_centered(|ui| {
+            ui.heading(RichText::new("Settings").strong());
+        });
+        ui.separator();
+
+        egui::ScrollArea::vertical()
+            .max_height(ui.available_height() - 60.0)
+            .show(ui, |ui| {
+                self.panel_section(ui);
+                self.rows_section(ui);
+                self.window_section(ui);
+                self.visibility_section(ui);
+                self.hotkeys_section(ui);
+            });
+
+        ui.separator();
+        for problem in self.draft.problems() {
+            ui.colored_label(
+                Color32::from_rgb(255, 80, 80),
+                RichText::new(problem).small(),
+            );
+        }
+        ui.horizontal(|ui| {
+            let can_save = self.path.is_some() && self.draft.problems().is_empty();
+            let save = ui.add_enabled(can_save, egui::Button::new("Save"));
+            let save = if self.path.is_none() {
+                save.on_disabled_hover_text("There's no config file to save to")
+            } else {
+                save
+            };
+            if save.clicked() {
+                self.save();
+            }
+            if ui.button("Close").clicked() {
+                close = true;
+            }
+            if let Some((ok, message)) = &self.message {
+                let color = if *ok {
+                    Color32::GREEN
+                } else {
+                    Color32::from_rgb(255, 80, 80)
+                };
+                ui.colored_label(color, RichText::new(message).small());
+            }
+        });
+        close
+    }
+
+    fn save(&mut self) {
+        let Some(path) = &self.path else {
+            return;
+        };
+        self.message = Some(match config::save(path, &self.draft) {
+            Ok(()) => (true, format!("Saved to {}", path.display())),
+            Err(e) => (false, format!("{:#}", e)),
+        });
+    }
+
+    fn panel_section(&mut self, ui: &mut egui::Ui) {
+        let panel = &mut self.draft.panel;
+        egui::CollapsingHeader::new("Look")
+            .default_open(true)
+            .show(ui, |ui| {
+                egui::Grid::new("settings_look")
+                    .num_columns(2)
+                    .show(ui, |ui| {
+                        ui.label("Title");
+                        ui.text_edit_singleline(&mut panel.title);
+                        ui.end_row();
+
+                        for (label, color) in [
+                            ("Title color", &mut panel.title_color),
+                            ("Label color", &mut panel.label_color),
+                            ("Value color", &mut panel.value_color),
+                        ] {
+                            ui.label(label);
+                            color_button(ui, color);
+                            ui.end_row();
+                        }
+
+                        ui.label("Font");
+                        ComboBox::from_id_salt("settings_font")
+                            .selected_text(font_name(panel.font))
+                            .show_ui(ui, |ui| {
+                                for font in Font::ALL {
+                                    ui.selectable_value(&mut panel.font, font, font_name(font));
+                                }
+                            });
+                        ui.end_row();
+
+                        ui.label("Font size");
+                        ui.add(Slider::new(&mut panel.font_size, 6.0..=48.0));
+                        ui.end_row();
+
+                        ui.label("Opacity");
+                        ui.add(Slider::new(&mut panel.opacity, 0.0..=1.0));
+                        ui.end_row();
+
+                        ui.label("Items");
+                        ui.checkbox(&mut panel.inventory, "Show your items under the stats");
+                        ui.end_row();
+                    });
+            });
+    }
+
+    fn rows_section(&mut self, ui: &mut egui::Ui) {
+        let panel = &mut self.draft.panel;
+        egui::CollapsingHeader::new("Stats")
+            .default_open(true)
+            .show(ui, |ui| {
+                ui.label(RichText::new("Left column").strong());
+                if let Some(row) = rows_editor(ui, "left", &mut panel.left, &mut panel.vars) {
+                    panel.right.push(row);
+                }
+                ui.add_space(6.0);
+                ui.label(RichText::new("Right column").strong());
+                if let Some(row) = rows_editor(ui, "right", &mut panel.right, &mut panel.vars) {
+                    panel.left.push(row);
+                }
+                ui.add_space(6.0);
+                vars_editor(ui, &mut panel.vars);
+            });
+    }
+
+    fn window_section(&mut self, ui: &mut egui::Ui) {
+        egui::CollapsingHeader::new("Panels").show(ui, |ui| {
+            for kind in PanelKind::ALL {
+                let window = self.draft.windows.get_mut(kind);
+                ui.horizontal(|ui| {
+                    ui.checkbox(&mut window.visible, RichText::new(kind.title()).strong());
+                });
+                egui::Grid::new(("settings_window", kind.name()))
+                    .num_columns(2)
+                    .show(ui, |ui| {
+                        ui.label("Anchor");
+                        ComboBox::from_id_salt(("settings_anchor", kind.name()))
+                            .selected_text(window.anchor.name())
+                            .show_ui(ui, |ui| {
+                                for anchor in Anchor::ALL {
+                                    ui.selectable_value(&mut window.anchor, anchor, anchor.name());
+                                }
+                            });
+                        ui.end_row();
+
+                        ui.label("Offset");
+                        ui.horizontal(|ui| {
+                            ui.add(DragValue::new(&mut window.offset[0]).prefix("x "));
+                            ui.add(DragValue::new(&mut window.offset[1]).prefix("y "));
+                        });
+                        ui.end_row();
+
+                        ui.label("Size");
+                        ui.horizontal(|ui| {
+                            ui.add(DragValue::new(&mut window.size[0]).range(50.0..=4000.0));
+                            ui.add(DragValue::new(&mut window.size[1]).range(50.0..=4000.0));
+                        });
+                        ui.end_row();
+
+                        ui.label("Hotkey");
+                        ui.text_edit_singleline(&mut window.hotkey);
+                        ui.end_row();
+                    });
+                ui.add_space(4.0);
+            }
+        });
+    }
+
+    fn visibility_section(&mut self, ui: &mut egui::Ui) {
+        let visibility = &mut self.draft.visibility;
+        egui::CollapsingHeader::new("Visibility").show(ui, |ui| {
+            ui.checkbox(
+                &mut visibility.hide_without_game,
+                "Hide while there's no game",
+            );
+            ui.checkbox(
+                &mut visibility.hide_after_game,
+                "Hide once the game is over",
+            );
+            ui.horizontal(|ui| {
+                ui.label("Hide without game data for");
+                ui.add(
+                    DragValue::new(&mut visibility.hide_after_no_data_secs)
+                        .range(0..=600)
+                        .suffix(" s"),
+                );
+                ui.label(RichText::new("0 never").small());
+            });
+            ui.add(
+                Slider::new(&mut visibility.fade_ms, 0..=1000)
+                    .text("Fade")
+                    .suffix(" ms"),
+            );
+        });
+    }
+
+    fn hotkeys_section(&mut self, ui: &mut egui::Ui) {
+        let hotkeys = &mut self.draft.hotkeys;
+        egui::CollapsingHeader::new("Hotkeys").show(ui, |ui| {
+            egui::Grid::new("settings_hotkeys")
+                .num_columns(2)
+                .show(ui, |ui| {
+                    for action in HotkeyAction::GLOBAL {
+                        if let Some(binding) = hotkeys.binding_mut(action) {
+                            ui.label(action.description());
+                            ui.text_edit_singleline(binding);
+                            ui.end_row();
+                        }
+                    }
+                });
+            ui.label(
+                RichText::new(
+                    "e.g. Ctrl+Shift+X, Alt+F9, leave empty for none. Each panel's is under Panels",
+                )
+                .small(),
+            );
+        });
+    }
+}
+
+/// One column's rows, hands back a row that should move to the other column
+fn rows_editor(
+    ui: &mut egui::Ui,
+    column: &str,
+    rows: &mut Vec<RowConfig>,
+    vars: &mut BTreeMap<String, String>,
+) -> Option<RowConfig> {
+    let mut edit = None;
+    let last = rows.len().saturating_sub(1);
+    for (i, row) in rows.iter_mut().enumerate() {
+        ui.push_id((column, i), |ui| {
+            ui.horizontal(|ui| {
+                ui.checkbox(&mut row.visible, "")
+                    .on_hover_text("Show this row");
+                if ui.add_enabled(i > 0, egui::Button::new("⏶")).clicked() {
+                    edit = Some(RowEdit::Up(i));
+                }
+                if ui.add_enabled(i < last, egui::Button::new("⏷")).clicked() {
+                    edit = Some(RowEdit::Down(i));
+                }
+                ui.add(egui::TextEdit::singleline(&mut row.icon).desired_width(24.0));
+                ui.add(egui::TextEdit::singleline(&mut row.label).desired_width(120.0));
+                color_button(ui, &mut row.color);
+                if ui
+                    .button("⇄")
+                    .on_hover_text("Move to the other column")
+                    .clicked()
+                {
+                    edit = Some(RowEdit::OtherColumn(i));
+                }
+                if ui.button("🗑").on_hover_text("Remove").clicked() {
+                    edit = Some(RowEdit::Remove(i));
+                }
+            });
+            ui.collapsing(RichText::new(&row.value).small(), |ui| {
+                egui::Grid::new("row").num_columns(2).show(ui, |ui| {
+                    ui.label("Value")
+                        .on_hover_text("e.g. {attack_speed:.2} atk/s");
+                    ui.text_edit_singleline(&mut row.value);
+                    ui.end_row();
+
+                    ui.label("Only when")
+                        .on_hover_text("e.g. lethality > 0, leave empty to always show the value");
+                    let mut when = row.when.clone().unwrap_or_default();
+                    if ui.text_edit_singleline(&mut when).changed() {
+                        row.when = Some(when).filter(|when| !when.trim().is_empty());
+                    }
+                    ui.end_row();
+
+                    ui.label("Otherwise");
+                    ui.text_edit_singleline(&mut row.otherwise);
+                    ui.end_row();
+
+                    ui.label("Image").on_hover_text(
+                        "stats/<image>.png in the icons folder, shown instead of the icon",
+                    );
+                    ui.text_edit_singleline(&mut row.image);
+                    ui.end_row();
+                });
+                rules_editor(ui, &mut row.rules);
+            });
+        });
+    }
+
+    // The rows the overlay comes with, plus an empty one to write from scratch
+    let defaults = PanelConfig::default();
+    ComboBox::from_id_salt(format!("settings_add_{}", column))
+        .selected_text("Add a stat...")
+        .show_ui(ui, |ui| {
+            for preset in defaults.left.iter().chain(&defaults.right) {
+                if ui.selectable_label(false, &preset.label).clicked() {
+                    // Bring along any of the default vars it leans on
+                    for (name, expr) in &defaults.vars {
+                        if preset.value.contains(name.as_str())
+                            || preset
+                                .when
+                                .as_ref()
+                                .is_some_and(|when| when.contains(name.as_str()))
+                        {
+                            vars.entry(name.clone()).or_insert_with(|| expr.clone());
+                        }
+                    }
+                    rows.push(preset.clone());
+                }
+            }
+            if ui.selectable_label(false, "Custom").clicked() {
+                rows.push(RowConfig::new("Custom:", "", "{level}", (200, 200, 200)));
+            }
+        });
+
+    match edit? {
+        RowEdit::Up(i) => rows.swap(i, i - 1),
+        RowEdit::Down(i) => rows.swap(i, i + 1),
+        RowEdit::OtherColumn(i) => return Some(rows.remove(i)),
+        RowEdit::Remove(i) => {
+            rows.remove(i);
+        }
+    }
+    None
+}
+
+// First rule that holds wins, so they can be moved around like rows
+fn rules_editor(ui: &mut egui::Ui, rules: &mut Vec<ColorRule>) {
+    ui.label("Colors").on_hover_text(
+        "The first rule that holds colors the value, e.g. cs_per_min < 6 or total_gold < 400 * minutes",
+    );
+    let mut edit = None;
+    let last = rules.len().saturating_sub(1);
+    for (i, rule) in rules.iter_mut().enumerate() {
+        ui.push_id(i, |ui| {
+            ui.horizontal(|ui| {
+                if ui.add_enabled(i > 0, egui::Button::new("⏶")).clicked() {
+                    edit = Some(RowEdit::Up(i));
+                }
+                if ui.add_enabled(i < last, egui::Button::new("⏷")).clicked() {
+                    edit = Some(RowEdit::Down(i));
+                }
+                ui.add(egui::TextEdit::singleline(&mut rule.when).desired_width(180.0));
+                let mut colored = rule.color.is_some();
+                if ui.checkbox(&mut colored, "").changed() {
+                    rule.color = colored.then_some(HexColor(Color32::WHITE));
+                }
+                if let Some(color) = &mut rule.color {
+                    color_button(ui, color);
+                }
+                ui.checkbox(&mut rule.flash, "Flash");
+                if ui.button("🗑").on_hover_text("Remove").clicked() {
+                    edit = Some(RowEdit::Remove(i));
+                }
+            });
+        });
+    }
+    if ui.button("Add rule").clicked() {
+        rules.push(ColorRule {
+            when: "true".to_string(),
+            color: Some(HexColor(Color32::WHITE)),
+            flash: false,
+        });
+    }
+    match edit {
+        Some(RowEdit::Up(i)) => rules.swap(i, i - 1),
+        Some(RowEdit::Down(i)) => rules.swap(i, i + 1),
+        Some(RowEdit::Remove(i)) => {
+            rules.remove(i);
+        }
+        Some(RowEdit::OtherColumn(_)) | None => {}
+    }
+}
+
+fn vars_editor(ui: &mut egui::Ui, vars: &mut BTreeMap<String, String>) {
+    ui.collapsing(RichText::new("Variables").strong(), |ui| {
+        let mut remove = None;
+        egui::Grid::new("settings_vars")
+            .num_columns(3)
+            .show(ui, |ui| {
+                for (name, expr) in vars.iter_mut() {
+                    ui.label(name.as_str());
+                    ui.text_edit_singleline(expr);
+                    if ui.button("🗑").on_hover_text("Remove").clicked() {
+                        remove = Some(name.clone());
+                    }
+                    ui.end_row();
+                }
+            });
+        if let Some(name) = remove {
+            vars.remove(&name);
+        }
+
+        // Name of the next one, kept between frames while it's being typed
+        let id = ui.id().with("new_var");
+        let mut new_name: String = ui.data_mut(|data| data.get_temp(id).unwrap_or_default());
+        ui.horizontal(|ui| {
+            ui.add(
+                egui::TextEdit::singleline(&mut new_name)
+                    .hint_text("name")
+                    .desired_width(120.0),
+            );
+            if ui
+                .add_enabled(!new_name.trim().is_empty(), egui::Button::new("Add"))
+                .clicked()
+            {
+                vars.entry(new_name.trim().to_string())
+                    .or_insert_with(|| "0".to_string());
+                new_name.clear();
+            }
+        });
+        ui.data_mut(|data| data.insert_temp(id, new_name));
+    });
+}
+
+fn color_button(ui: &mut egui::Ui, color: &mut HexColor) {
+    ui.color_edit_button_srgba(&mut color.0);
+}
+
+fn font_name(font: Font) -> &'static str {
+    match font {
+        Font::Monospace => "Monospace",
+        Font::Proportional => "Proportional",
+    }
+}
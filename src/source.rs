@@ -0,0 +1,219 @@
+use crate::cli::Args;
+use crate::config::{self, ConfigReceiver};
+use crate::replay::{ReplayCommand, replay_fetcher};
+use anyhow::{Result, bail};
+use league_overlay::event_cursor::EventCursor;
+use league_overlay::fetcher::{FetcherConfig, game_data_fetcher, send_snapshot};
+use league_overlay::mock::synthetic::ScriptedGame;
+use league_overlay::recorder::{RecordLine, Recorder};
+use league_overlay::schema::{self, SchemaReport};
+use league_overlay::{GameInfo, GameUpdate, LoLLiveClient};
+use serde_json::Value;
+use std::future::Future;
+use std::path::PathBuf;
+use std::pin::Pin;
+use tokio::io::{AsyncBufReadExt, BufReader};
+use tokio::sync::{mpsc, watch};
+
+/*
+Where the overlay's game data comes from. The overlay only ever sees GameUpdates on a channel,
+so anything that can produce them can be plugged in here without touching the UI.
+    live     League's Live Client API (default)
+    replay   a recording made with --record (--replay <path>)
+    script   a scripted fake game (--script <path>, same files as mock_live_client --script)
+    stdin    allgamedata json, one per line (--stdin)
+*/
+
+pub type UpdateSender = mpsc::UnboundedSender<GameUpdate>;
+pub type SourceFuture = Pin<Box<dyn Future<Output = Result<()>> + Send>>;
+
+pub trait GameDataSource: Send {
+    fn name(&self) -> String;
+
+    /// Sends updates until the source runs dry or the receiver goes away
+    fn run(self: Box<Self>, sender: UpdateSender) -> SourceFuture;
+
+    /// Where replay hotkeys are sent, None for sources that can't be paused or seeked
+    fn replay_controls(&self) -> Option<mpsc::UnboundedSender<ReplayCommand>> {
+        None
+    }
+}
+
+/// Picks the source asked for on the command line
+pub fn from_args(args: &Args, config: ConfigReceiver) -> Result<Box<dyn GameDataSource>> {
+    let picked = [args.replay.is_some(), args.script.is_some(), args.stdin]
+        .iter()
+        .filter(|picked| **picked)
+        .count();
+    if picked > 1 {
+        bail!("Only one of --replay, --script and --stdin can be used at a time");
+    }
+    if args.record.is_some() && picked > 0 {
+        bail!("--record only works with the live game");
+    }
+    if args.cert_trust.is_some() && picked > 0 {
+        bail!("--ca-cert and --insecure only work with the live game");
+    }
+
+    let source: Box<dyn GameDataSource> = if let Some(path) = &args.replay {
+        let (controls, commands) = mpsc::unbounded_channel();
+        stdin_replay_controls(controls.clone());
+        Box::new(ReplaySource {
+            path: path.clone(),
+            speed: args.speed.unwrap_or(1.0),
+            controls,
+            commands,
+        })
+    } else if let Some(path) = &args.script {
+        Box::new(ScriptSource {
+            game: ScriptedGame::load(path, args.speed)?,
+            path: path.clone(),
+        })
+    } else if args.stdin {
+        Box::new(StdinSource)
+    } else {
+        let client = LoLLiveClient::new(&config.borrow().config.live_client)?;
+        Box::new(LiveSource {
+            client,
+            recorder: args.record.as_deref().map(Recorder::create).transpose()?,
+            config: config::fetcher_config(config),
+        })
+    };
+    Ok(source)
+}
+
+pub struct LiveSource {
+    client: LoLLiveClient,
+    recorder: Option<Recorder>,
+    config: watch::Receiver<FetcherConfig>,
+}
+
+impl GameDataSource for LiveSource {
+    fn name(&self) -> String {
+        "Live Client API".to_string()
+    }
+
+    fn run(self: Box<Self>, sender: UpdateSender) -> SourceFuture {
+        Box::pin(game_data_fetcher(
+            sender,
+            self.client,
+            self.recorder,
+            self.config,
+        ))
+    }
+}
+
+pub struct ReplaySource {
+    path: PathBuf,
+    speed: f64,
+    controls: mpsc::UnboundedSender<ReplayCommand>,
+    commands: mpsc::UnboundedReceiver<ReplayCommand>,
+}
+
+impl GameDataSource for ReplaySource {
+    fn name(&self) -> String {
+        format!("replay of {}", self.path.display())
+    }
+
+    fn run(self: Box<Self>, sender: UpdateSender) -> SourceFuture {
+        Box::pin(async move {
+            let ReplaySource {
+                path,
+                speed,
+                controls,
+                commands,
+            } = *self;
+            // Only the overlay and stdin hold on to one, so it can tell when nobody's left to send
+            drop(controls);
+            replay_fetcher(&path, sender, commands, speed).await
+        })
+    }
+
+    fn replay_controls(&self) -> Option<mpsc::UnboundedSender<ReplayCommand>> {
+        Some(self.controls.clone())
+    }
+}
+
+pub struct ScriptSource {
+    path: PathBuf,
+    game: ScriptedGame,
+}
+
+impl GameDataSource for ScriptSource {
+    fn name(&self) -> String {
+        format!("script {} at {}x", self.path.display(), self.game.speed())
+    }
+
+    fn run(self: Box<Self>, sender: UpdateSender) -> SourceFuture {
+        Box::pin(async move {
+            let mut cursor = EventCursor::new();
+            loop {
+                let game_info = serde_json::from_value::<GameInfo>(self.game.snapshot_now())?;
+                if send_snapshot(&sender, &mut cursor, game_info).is_err() {
+                    return Ok(());
+                }
+                tokio::time::sleep(tokio::time::Duration::from_millis(1000)).await;
+            }
+        })
+    }
+}
+
+pub struct StdinSource;
+
+impl GameDataSource for StdinSource {
+    fn name(&self) -> String {
+        "stdin".to_string()
+    }
+
+    fn run(self: Box<Self>, sender: UpdateSender) -> SourceFuture {
+        Box::pin(async move {
+            let mut cursor = EventCursor::new();
+            let mut report = SchemaReport::default();
+            let mut lines = BufReader::new(tokio::io::stdin()).lines();
+            while let Some(line) = lines.next_line().await? {
+                if line.trim().is_empty() {
+                    continue;
+                }
+                match parse_stdin_line(&line, &mut report) {
+                    Ok(game_info) => {
+                        if send_snapshot(&sender, &mut cursor, game_info).is_err() {
+                            break;
+                        }
+                    }
+                    Err(e) => println!("Skipping stdin line: {}", e),
+                }
+            }
+            println!("stdin closed, no more game data");
+            Ok(())
+        })
+    }
+}
+
+//Either bare allgamedata or a line from a recording
+fn parse_stdin_line(line: &str, report: &mut SchemaReport) -> Result<GameInfo> {
+    let value: Value = serde_json::from_str(line)?;
+    let data = match serde_json::from_value::<RecordLine>(value.clone()) {
+        Ok(RecordLine::Snapshot { data, .. }) => data,
+        Ok(RecordLine::Header(_)) => bail!("recording header"),
+        Err(_) => value,
+    };
+    Ok(schema::parse_lenient("stdin", data, report)?)
+}
+
+// Replay controls can also be typed into the terminal (pause, speed 4, seek 12:30),
+// where there is one: the Windows build has no console
+fn stdin_replay_controls(sender: mpsc::UnboundedSender<ReplayCommand>) {
+    std::thread::spawn(move || {
+        for line in std::io::stdin().lines() {
+            let Ok(line) = line else { break };
+            match line.parse::<ReplayCommand>() {
+                Ok(command) => {
+                    if sender.send(command).is_err() {
+                        break;
+                    }
+                }
+                Err(e) => println!("{}", e),
+            }
+        }
+    });
+}
@@ -0,0 +1,400 @@
+use crate::config::{ColorRule, PanelConfig, RowConfig};
+use crate::expr::{Expr, Template, Vars};
+use anyhow::{Context, Result, bail};
+use league_overlay::GameInfo;
+
+/*
+What the rows in the [[panel.left]] / [[panel.right]] config sections show.
+Each row's value is a Template (see expr.rs) over the variables in VARIABLES, worked out fresh from
+every GameInfo, plus whatever [panel.vars] defines on top, e.g. armor_pen_pct = "100 * (1 - armor_pen)".
+A row with `when` shows `otherwise` instead of its value while the condition is false.
+Its `rules` are checked top to bottom and the first one that holds colors (or flashes) the value,
+targets that grow with the game are just expressions over minutes, e.g. total_gold < 400 * minutes.
+The whole panel is compiled when the config loads, a mistake in it is a config error.
+*/
+
+/// Every variable a row can use, named like the fields they come from
+pub const VARIABLES: &[&str] = &[
+    // ChampionStats
+    "ability_haste",
+    "ability_power",
+    "armor",
+    "lethality",
+    "armor_pen",
+    "attack_damage",
+    "attack_range",
+    "attack_speed",
+    "bonus_armor_pen",
+    "bonus_magic_pen",
+    "crit_chance",
+    "crit_damage",
+    "current_health",
+    "heal_shield_power",
+    "health_regen_rate",
+    "life_steal",
+    "magic_lethality",
+    "magic_pen",
+    "magic_pen_percent",
+    "magic_resist",
+    "max_health",
+    "move_speed",
+    "omnivamp",
+    "physical_lethality",
+    "physical_vamp",
+    "resource_max",
+    "resource_regen_rate",
+    "resource_value",
+    "spell_vamp",
+    "tenacity",
+    // ActivePlayer
+    "level",
+    "current_gold",
+    // Our entry in the player list
+    "kills",
+    "deaths",
+    "assists",
+    "creep_score",
+    "ward_score",
+    "is_dead",
+    "respawn_timer",
+    "item_gold",
+    // Game time, and a couple everyone wants
+    "game_time",
+    "minutes",
+    "cs_per_min",
+    "total_gold",
+];
+
+/// The variables for the active player, None until they show up in the player list
+pub fn variables(game_info: &GameInfo) -> Option<Vars> {
+    let player = &game_info.active_player;
+    let stats = &player.champion_stats;
+    let entry = game_info
+        .all_players
+        .iter()
+        .find(|p| p.riot_id == player.riot_id)?;
+    let scores = &entry.scores;
+    let minutes = game_info.game_data.game_time / 60.0;
+    let item_gold: f64 = entry.items.iter().map(|item| item.price as f64).sum();
+
+    let values = [
+        stats.ability_haste,
+        stats.ability_power,
+        stats.armor,
+        stats.lethality,
+        stats.armor_pen,
+        stats.attack_damage,
+        stats.attack_range,
+        stats.attack_speed,
+        stats.bonus_armor_pen,
+        stats.bonus_magic_pen,
+        stats.crit_chance,
+        stats.crit_damage,
+        stats.current_health,
+        stats.heal_shield_power,
+        stats.health_regen_rate,
+        stats.life_steal,
+        stats.magic_lethality,
+        stats.magic_pen,
+        stats.magic_pen_percent,
+        stats.magic_resist,
+        stats.max_health,
+        stats.move_speed,
+        stats.omnivamp,
+        stats.physical_lethality,
+        stats.physical_vamp,
+        stats.resource_max,
+        stats.resource_regen_rate,
+        stats.resource_value,
+        stats.spell_vamp,
+        stats.tenacity,
+        player.level as f64,
+        player.current_gold,
+        scores.kills as f64,
+        scores.deaths as f64,
+        scores.assists as f64,
+        scores.creep_score as f64,
+        scores.ward_score as f64,
+        if entry.is_dead { 1.0 } else { 0.0 },
+        entry.respawn_timer as f64,
+        item_gold,
+        game_info.game_data.game_time,
+        minutes,
+        scores.creep_score as f64 / minutes,
+        player.current_gold + item_gold,
+    ];
+    Some(
+        VARIABLES
+            .iter()
+            .map(|name| name.to_string())
+            .zip(values)
+            .collect(),
+    )
+}
+
+pub struct CompiledPanel {
+    //[panel.vars], in an order where each only needs the ones before it
+    derived: Vec<(String, Expr)>,
+    pub left: Vec<CompiledRow>,
+    pub right: Vec<CompiledRow>,
+}
+
+pub struct CompiledRow {
+    pub config: RowConfig,
+    value: Template,
+    when: Option<Expr>,
+    rules: Vec<(Expr, ColorRule)>,
+}
+
+impl CompiledRow {
+    /// The first rule that holds right now, None if the value looks like usual
+    pub fn rule(&self, vars: &Vars) -> Option<&ColorRule> {
+        self.rules
+            .iter()
+            .find(|(when, _)| when.is_true(vars))
+            .map(|(_, rule)| rule)
+    }
+
+    pub fn text(&self, vars: &Vars) -> String {
+        match &self.when {
+            Some(when) if !when.is_true(vars) => self.config.otherwise.clone(),
+            _ => self.value.render(vars),
+        }
+    }
+}
+
+impl CompiledPanel {
+    pub fn compile(panel: &PanelConfig) -> Result<Self> {
+        let derived = order_derived(panel)?;
+        let mut known: Vec<&str> = VARIABLES.to_vec();
+        known.extend(derived.iter().map(|(name, _)| name.as_str()));
+
+        let compile_rows = |rows: &[RowConfig], column: &str| -> Result<Vec<CompiledRow>> {
+            rows.iter()
+                .map(|row| {
+                    let compiled = compile_row(row, &known)
+                        .with_context(|| format!("{} row {:?}", column, row.label))?;
+                    Ok(compiled)
+                })
+                .collect()
+        };
+        let left = compile_rows(&panel.left, "left")?;
+        let right = compile_rows(&panel.right, "right")?;
+        Ok(Self {
+            derived,
+            left,
+            right,
+        })
+    }
+
+    /// The game's variables with [panel.vars] worked out on top
+    pub fn vars(&self, mut vars: Vars) -> Vars {
+        for (name, expr) in &self.derived {
+            let value = expr.eval(&vars);
+            vars.insert(name.clone(), value);
+        }
+        vars
+    }
+}
+
+fn compile_row(row: &RowConfig, known: &[&str]) -> Result<CompiledRow> {
+    let value = Template::parse(&row.value).context("value")?;
+    check_variables(value.variables(), known).context("value")?;
+    let when = match &row.when {
+        Some(when) => {
+            let expr = Expr::parse(when).context("when")?;
+            check_variables(expr.variables(), known).context("when")?;
+            Some(expr)
+        }
+        None => None,
+    };
+    let rules = row
+        .rules
+        .iter()
+        .enumerate()
+        .map(|(i, rule)| {
+            let context = || format!("rule {}", i + 1);
+            let expr = Expr::parse(&rule.when).with_context(context)?;
+            check_variables(expr.variables(), known).with_context(context)?;
+            Ok((expr, rule.clone()))
+        })
+        .collect::<Result<_>>()?;
+    Ok(CompiledRow {
+        config: row.clone(),
+        value,
+        when,
+        rules,
+    })
+}
+
+fn check_variables(used: Vec<&str>, known: &[&str]) -> Result<()> {
+    if let Some(unknown) = used.into_iter().find(|name| !known.contains(name)) {
+        bail!("Unknown variable {:?}", unknown);
+    }
+    Ok(())
+}
+
+// Vars can use each other, so keep picking ones whose inputs are all known until none are left
+fn order_derived(panel: &PanelConfig) -> Result<Vec<(String, Expr)>> {
+    let mut pending = Vec::new();
+    for (name, text) in &panel.vars {
+        if VARIABLES.contains(&name.as_str()) {
+            bail!("vars.{} would hide the built in variable", name);
+        }
+        if !name.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_')
+            || !name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+        {
+            bail!("vars.{} isn't a usable name (letters, digits and _)", name);
+        }
+        let expr = Expr::parse(text).with_context(|| format!("vars.{}", name))?;
+        pending.push((name.clone(), expr));
+    }
+
+    let mut known: Vec<&str> = VARIABLES.to_vec();
+    known.extend(panel.vars.keys().map(String::as_str));
+    for (name, expr) in &pending {
+        check_variables(expr.variables(), &known).with_context(|| format!("vars.{}", name))?;
+    }
+
+    let mut ordered: Vec<(String, Expr)> = Vec::new();
+    while !pending.is_empty() {
+        let ready = pending.iter().position(|(_, expr)| {
+            expr.variables().iter().all(|used| {
+                VARIABLES.contains(used) || ordered.iter().any(|(name, _)| name == used)
+            })
+        });
+        match ready {
+            Some(i) => ordered.push(pending.remove(i)),
+            None => {
+                let stuck: Vec<&str> = pending.iter().map(|(name, _)| name.as_str()).collect();
+                bail!("vars {} need each other", stuck.join(", "));
+            }
+        }
+    }
+    Ok(ordered)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::HexColor;
+    use eframe::egui::Color32;
+
+    fn panel(vars: &[(&str, &str)], value: &str) -> PanelConfig {
+        let mut panel = PanelConfig {
+            vars: vars
+                .iter()
+                .map(|(name, text)| (name.to_string(), text.to_string()))
+                .collect(),
+            right: Vec::new(),
+            ..PanelConfig::default()
+        };
+        panel.left.truncate(1);
+        panel.left[0].value = value.to_string();
+        panel.left[0].when = None;
+        panel.left[0].rules.clear();
+        panel
+    }
+
+    // Every game variable at 0 but the ones given, with the default [panel.vars] on top
+    fn default_vars(compiled: &CompiledPanel, values: &[(&str, f64)]) -> Vars {
+        let mut vars: Vars = VARIABLES
+            .iter()
+            .map(|name| (name.to_string(), 0.0))
+            .collect();
+        for (name, value) in values {
+            vars.insert(name.to_string(), *value);
+        }
+        compiled.vars(vars)
+    }
+
+    #[test]
+    fn the_first_rule_that_holds_wins() {
+        let compiled = CompiledPanel::compile(&PanelConfig::default()).unwrap();
+        let cs_per_min = &compiled.left[4];
+        let color = |minutes: f64, cs: f64| {
+            let vars = default_vars(&compiled, &[("minutes", minutes), ("cs_per_min", cs)]);
+            cs_per_min.rule(&vars).map(|rule| rule.color)
+        };
+        let rgb = |r, g, b| Some(Some(HexColor(Color32::from_rgb(r, g, b))));
+
+        // Everything under 6 is also under 8, but the red one comes first
+        assert_eq!(color(10.0, 5.0), rgb(255, 80, 80));
+        assert_eq!(color(10.0, 7.0), rgb(255, 215, 0));
+        assert_eq!(color(10.0, 9.0), rgb(100, 255, 100));
+        // The grace period is first of all, it holds with no color
+        assert_eq!(color(2.0, 1.0), Some(None));
+        assert!(
+            compiled.left[0]
+                .rule(&default_vars(&compiled, &[]))
+                .is_none()
+        );
+    }
+
+    #[test]
+    fn the_default_rows_nag_until_there_is_some_pen() {
+        let compiled = CompiledPanel::compile(&PanelConfig::default()).unwrap();
+        let (lethality, magic_pen) = (&compiled.right[0], &compiled.right[1]);
+
+        // What the API says before any pen is bought
+        let vars = default_vars(&compiled, &[("armor_pen", 1.0), ("magic_pen_percent", 1.0)]);
+        assert_eq!(lethality.text(&vars), "Farm!!!");
+        assert_eq!(magic_pen.text(&vars), "Keep it up!");
+
+        let vars = default_vars(
+            &compiled,
+            &[
+                ("lethality", 10.0),
+                ("physical_lethality", 10.0),
+                ("armor_pen", 0.8),
+                ("magic_lethality", 5.0),
+                ("magic_pen", 3.0),
+                ("magic_pen_percent", 0.9),
+            ],
+        );
+        assert_eq!(lethality.text(&vars), "10 | %20.00");
+        assert_eq!(magic_pen.text(&vars), "8 | %10.00");
+
+        // Percent pen alone is enough to stop either
+        let vars = default_vars(&compiled, &[("armor_pen", 0.7), ("magic_pen_percent", 0.6)]);
+        assert_eq!(lethality.text(&vars), "0 | %30.00");
+        assert_eq!(magic_pen.text(&vars), "0 | %40.00");
+    }
+
+    #[test]
+    fn vars_are_worked_out_after_the_ones_they_use() {
+        let compiled = CompiledPanel::compile(&panel(
+            &[("a", "b * 2"), ("b", "c + 1"), ("c", "kills")],
+            "{a}",
+        ))
+        .unwrap();
+        let vars = compiled.vars(Vars::from([("kills".to_string(), 3.0)]));
+        assert_eq!(compiled.left[0].text(&vars), "8");
+    }
+
+    #[test]
+    fn vars_that_need_each_other_are_an_error() {
+        let error = CompiledPanel::compile(&panel(
+            &[("a", "b + 1"), ("b", "a + 1"), ("c", "kills")],
+            "{c}",
+        ))
+        .err()
+        .unwrap();
+        assert_eq!(error.to_string(), "vars a, b need each other");
+        assert!(CompiledPanel::compile(&panel(&[("a", "a")], "{a}")).is_err());
+    }
+
+    #[test]
+    fn unknown_variables_are_an_error() {
+        let error = CompiledPanel::compile(&panel(&[], "{kils}")).err().unwrap();
+        assert_eq!(
+            format!("{:#}", error),
+            format!(
+                "left row {:?}: value: Unknown variable \"kils\"",
+                PanelConfig::default().left[0].label
+            )
+        );
+        assert!(CompiledPanel::compile(&panel(&[("a", "nope")], "{a}")).is_err());
+        assert!(CompiledPanel::compile(&panel(&[("kills", "1")], "{kills}")).is_err());
+    }
+}
@@ -0,0 +1,239 @@
+use anyhow::{Context, Result, bail};
+use rustls::client::WebPkiServerVerifier;
+use rustls::client::danger::{HandshakeSignatureValid, ServerCertVerified, ServerCertVerifier};
+use rustls::pki_types::pem::PemObject;
+use rustls::pki_types::{CertificateDer, ServerName, UnixTime};
+use rustls::{
+    CertificateError, ClientConfig, DigitallySignedStruct, RootCertStore, SignatureScheme,
+};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+/*
+Who LoLLiveClient trusts on https://127.0.0.1:2999.
+The game signs its cert with Riot's own root (src/assets/riotgames.pem, published at RIOT_ROOT_URL),
+so that's the only root we accept unless another one is passed with --ca-cert.
+The game's cert only has 127.0.0.1 as its common name (no subject alt names), which webpki never matches,
+so a name mismatch is let through as long as the chain checks out against the pinned root.
+--insecure skips all of this like the overlay used to, and the overlay says so on screen.
+*/
+
+const RIOT_ROOT_PEM: &[u8] = include_bytes!("assets/riotgames.pem");
+const RIOT_ROOT_URL: &str = "https://static.developer.riotgames.com/docs/lol/riotgames.pem";
+
+/// Which root certificate the Live Client's certificate has to chain to
+#[derive(Debug, Clone, Default, PartialEq)]
+pub enum CertTrust {
+    /// Riot's root certificate, bundled with the overlay
+    #[default]
+    Riot,
+    /// A root of our own, for the mock server or if Riot ever rotates theirs
+    CaFile(PathBuf),
+    /// Accept any certificate
+    Insecure,
+}
+
+/// rustls config that only trusts the pinned root, None when verification is off
+pub fn client_config(trust: &CertTrust) -> Result<Option<ClientConfig>> {
+    let roots = match trust {
+        CertTrust::Riot => root_store(RIOT_ROOT_PEM).with_context(|| {
+            format!(
+                "The bundled Riot root certificate isn't usable, point --ca-cert at a copy of {} \
+                 or run with --insecure",
+                RIOT_ROOT_URL
+            )
+        })?,
+        CertTrust::CaFile(path) => read_root_store(path)?,
+        CertTrust::Insecure => return Ok(None),
+    };
+
+    let provider = Arc::new(rustls::crypto::ring::default_provider());
+    let verifier = PinnedRootVerifier {
+        inner: WebPkiServerVerifier::builder_with_provider(Arc::new(roots), provider.clone())
+            .build()?,
+    };
+    let config = ClientConfig::builder_with_provider(provider)
+        .with_safe_default_protocol_versions()?
+        .dangerous()
+        .with_custom_certificate_verifier(Arc::new(verifier))
+        .with_no_client_auth();
+    Ok(Some(config))
+}
+
+fn read_root_store(path: &Path) -> Result<RootCertStore> {
+    let pem = std::fs::read(path).with_context(|| format!("Failed to read {}", path.display()))?;
+    root_store(&pem).with_context(|| format!("{} isn't a usable root certificate", path.display()))
+}
+
+fn root_store(pem: &[u8]) -> Result<RootCertStore> {
+    let mut roots = RootCertStore::empty();
+    for cert in CertificateDer::pem_slice_iter(pem) {
+        roots.add(cert?)?;
+    }
+    if roots.is_empty() {
+        bail!("no certificates found");
+    }
+    Ok(roots)
+}
+
+#[derive(Debug)]
+struct PinnedRootVerifier {
+    inner: Arc<WebPkiServerVerifier>,
+}
+
+impl ServerCertVerifier for PinnedRootVerifier {
+    fn verify_server_cert(
+        &self,
+        end_entity: &CertificateDer<'_>,
+        intermediates: &[CertificateDer<'_>],
+        server_name: &ServerName<'_>,
+        ocsp_response: &[u8],
+        now: UnixTime,
+    ) -> Result<ServerCertVerified, rustls::Error> {
+        match self.inner.verify_server_cert(
+            end_entity,
+            intermediates,
+            server_name,
+            ocsp_response,
+            now,
+        ) {
+            // The chain is fine, it's just the missing subject alt name (see the top of the file)
+            Err(rustls::Error::InvalidCertificate(
+                CertificateError::NotValidForName | CertificateError::NotValidForNameContext { .. },
+            )) => Ok(ServerCertVerified::assertion()),
+            result => result,
+        }
+    }
+
+    fn verify_tls12_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, rustls::Error> {
+        self.inner.verify_tls12_signature(message, cert, dss)
+    }
+
+    fn verify_tls13_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, rustls::Error> {
+        self.inner.verify_tls13_signature(message, cert, dss)
+    }
+
+    fn supported_verify_schemes(&self) -> Vec<SignatureScheme> {
+        self.inner.supported_verify_schemes()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::live_client::{LiveClientConfig, LiveClientError, LoLLiveClient};
+    use axum_server::tls_rustls::RustlsConfig;
+    use std::io::Write;
+
+    // A root and a leaf shaped like the game's, 127.0.0.1 in the common name and no subject alt names
+    fn game_like_certs(root_name: &str) -> (rcgen::Certificate, rcgen::Certificate) {
+        let mut root = rcgen::CertificateParams::default();
+        root.is_ca = rcgen::IsCa::Ca(rcgen::BasicConstraints::Unconstrained);
+        root.distinguished_name = rcgen::DistinguishedName::new();
+        root.distinguished_name
+            .push(rcgen::DnType::CommonName, root_name);
+
+        let mut leaf = rcgen::CertificateParams::default();
+        leaf.distinguished_name = rcgen::DistinguishedName::new();
+        leaf.distinguished_name
+            .push(rcgen::DnType::CommonName, "127.0.0.1");
+
+        (
+            rcgen::Certificate::from_params(root).unwrap(),
+            rcgen::Certificate::from_params(leaf).unwrap(),
+        )
+    }
+
+    // Serves /liveclientdata/gamestats over TLS with `leaf` signed by `root`, returns the base url
+    async fn serve(root: &rcgen::Certificate, leaf: &rcgen::Certificate) -> String {
+        let _ = rustls::crypto::ring::default_provider().install_default();
+        let tls = RustlsConfig::from_pem(
+            leaf.serialize_pem_with_signer(root).unwrap().into_bytes(),
+            leaf.serialize_private_key_pem().into_bytes(),
+        )
+        .await
+        .unwrap();
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        let router = axum::Router::new().route(
+            "/liveclientdata/gamestats",
+            axum::routing::get(|| async { axum::Json(serde_json::json!({ "gameTime": 12.5 })) }),
+        );
+        tokio::spawn(axum_server::from_tcp_rustls(listener, tls).serve(router.into_make_service()));
+        format!("https://{}/liveclientdata", addr)
+    }
+
+    fn client(url: String, trust: &CertTrust) -> LoLLiveClient {
+        let mut config = LiveClientConfig {
+            url,
+            ..LiveClientConfig::default()
+        };
+        config.set_cert_trust(trust);
+        LoLLiveClient::new(&config).unwrap()
+    }
+
+    fn pem_file(cert: &rcgen::Certificate) -> tempfile::NamedTempFile {
+        let mut file = tempfile::NamedTempFile::new().unwrap();
+        file.write_all(cert.serialize_pem().unwrap().as_bytes())
+            .unwrap();
+        file
+    }
+
+    #[tokio::test]
+    async fn the_pinned_root_is_trusted() {
+        let (root, leaf) = game_like_certs("Test Root");
+        let url = serve(&root, &leaf).await;
+        let root_file = pem_file(&root);
+
+        let client = client(url, &CertTrust::CaFile(root_file.path().to_path_buf()));
+        let game_stats = client.get_json("/gamestats").await.unwrap();
+        assert_eq!(game_stats["gameTime"], 12.5);
+    }
+
+    #[tokio::test]
+    async fn another_root_is_turned_down() {
+        let (root, leaf) = game_like_certs("Test Root");
+        let url = serve(&root, &leaf).await;
+        let (other_root, _) = game_like_certs("Someone Else");
+        let root_file = pem_file(&other_root);
+
+        let client = client(url, &CertTrust::CaFile(root_file.path().to_path_buf()));
+        let error = client.get_json("/gamestats").await.unwrap_err();
+        assert!(matches!(error, LiveClientError::Tls { .. }), "{}", error);
+    }
+
+    #[tokio::test]
+    async fn insecure_takes_any_cert() {
+        let (root, leaf) = game_like_certs("Test Root");
+        let url = serve(&root, &leaf).await;
+
+        let client = client(url, &CertTrust::Insecure);
+        assert!(client.get_json("/gamestats").await.is_ok());
+    }
+
+    #[test]
+    fn the_bundled_riot_root_is_one_certificate() {
+        let certs: Vec<_> = CertificateDer::pem_slice_iter(RIOT_ROOT_PEM)
+            .collect::<Result<_, _>>()
+            .unwrap();
+        assert_eq!(certs.len(), 1);
+        assert!(client_config(&CertTrust::Riot).unwrap().is_some());
+    }
+
+    #[test]
+    fn a_file_without_certificates_is_refused() {
+        let mut file = tempfile::NamedTempFile::new().unwrap();
+        file.write_all(b"# nothing here\n").unwrap();
+        assert!(client_config(&CertTrust::CaFile(file.path().to_path_buf())).is_err());
+    }
+}
@@ -0,0 +1,193 @@
+use eframe::egui;
+use league_overlay::connection::ConnectionState;
+use serde::{Deserialize, Serialize};
+
+/*
+Whether the overlay is up. The toggle_overlay hotkey hides/shows it by hand, and the rules in the
+[visibility] section of the config can hide it on their own: while there's no game, on the post-game
+screen, or when no game data has come in for a while. Pressing the hotkey while a rule has it hidden
+shows it anyway, until that rule stops applying.
+Panel windows are hidden for real (ViewportCommand::Visible) once they've faded out, and shown again
+where they were, so nothing has to put them back into place. The main window only goes empty and
+click-through: it's the root viewport, and a hidden root stops being repainted on Windows.
+*/
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct VisibilityConfig {
+    //How long fading in or out takes, 0 to just pop in and out
+    pub fade_ms: u64,
+    //While League isn't running or the game is still loading
+    pub hide_without_game: bool,
+    //Once the game has ended
+    pub hide_after_game: bool,
+    //After this many seconds without game data, 0 to never
+    pub hide_after_no_data_secs: u64,
+}
+
+impl Default for VisibilityConfig {
+    // Nothing hides by itself unless asked to, that's how the overlay has always been
+    fn default() -> Self {
+        Self {
+            fade_ms: 150,
+            hide_without_game: false,
+            hide_after_game: false,
+            hide_after_no_data_secs: 0,
+        }
+    }
+}
+
+impl VisibilityConfig {
+    /// Settings that can't work, empty if everything is fine
+    pub fn problems(&self) -> Vec<String> {
+        let mut problems = Vec::new();
+        if self.fade_ms > 5000 {
+            problems.push("fade_ms can't be more than 5000".to_string());
+        }
+        problems
+    }
+}
+
+/// Why a rule has the overlay hidden
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AutoHide {
+    NoGame,
+    PostGame,
+    NoData,
+}
+
+impl AutoHide {
+    pub fn description(&self) -> &'static str {
+        match self {
+            AutoHide::NoGame => "no game running",
+            AutoHide::PostGame => "the game is over",
+            AutoHide::NoData => "no game data coming in",
+        }
+    }
+}
+
+/// What the rules go by, gathered by the overlay every frame
+pub struct GameState {
+    //Only the live source knows, None for the rest
+    pub connection: Option<ConnectionState>,
+    pub has_game: bool,
+    //A GameEnd event came in
+    pub game_ended: bool,
+}
+
+#[derive(Debug, Default)]
+pub struct Visibility {
+    //Hidden with the toggle_overlay hotkey
+    user_hidden: bool,
+    auto_hidden: Option<AutoHide>,
+    //Shown with the hotkey while this rule had it hidden
+    overridden: Option<AutoHide>,
+    //egui time of the last snapshot
+    last_data: Option<f64>,
+}
+
+impl Visibility {
+    pub fn is_visible(&self) -> bool {
+        !self.user_hidden && (self.auto_hidden.is_none() || self.auto_hidden == self.overridden)
+    }
+
+    pub fn data_received(&mut self, now: f64) {
+        self.last_data = Some(now);
+    }
+
+    /// Goes through the rules again
+    pub fn update(&mut self, config: &VisibilityConfig, game: &GameState, now: f64) {
+        let no_game = match game.connection {
+            Some(state) => matches!(state, ConnectionState::NoClient | ConnectionState::Loading),
+            None => !game.has_game,
+        };
+        let post_game = game.connection == Some(ConnectionState::PostGame) || game.game_ended;
+        let no_data = config.hide_after_no_data_secs > 0
+            && self
+                .last_data
+                .is_some_and(|last| now - last > config.hide_after_no_data_secs as f64);
+
+        let auto_hidden = if config.hide_without_game && no_game {
+            Some(AutoHide::NoGame)
+        } else if config.hide_after_game && post_game {
+            Some(AutoHide::PostGame)
+        } else if no_data {
+            Some(AutoHide::NoData)
+        } else {
+            None
+        };
+        if auto_hidden != self.auto_hidden {
+            match auto_hidden {
+                Some(reason) => println!("Hiding the overlay, {}", reason.description()),
+                None => println!("Nothing hides the overlay anymore"),
+            }
+            self.auto_hidden = auto_hidden;
+        }
+        if self.overridden.is_some() && self.overridden != self.auto_hidden {
+            self.overridden = None;
+        }
+    }
+
+    /// The toggle_overlay hotkey
+    pub fn toggle(&mut self) {
+        if self.is_visible() {
+            self.user_hidden = true;
+            self.overridden = None;
+        } else {
+            self.user_hidden = false;
+            self.overridden = self.auto_hidden;
+        }
+        println!("Toggling overlay visibility to: {}", self.is_visible());
+    }
+}
+
+/// How far faded in something is, 0 (gone, its window can be hidden) to 1
+pub fn fade(ctx: &egui::Context, id: egui::Id, shown: bool, config: &VisibilityConfig) -> f32 {
+    ctx.animate_bool_with_time(id.with("fade"), shown, config.fade_ms as f32 / 1000.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn game(connection: ConnectionState) -> GameState {
+        GameState {
+            connection: Some(connection),
+            has_game: true,
+            game_ended: false,
+        }
+    }
+
+    #[test]
+    fn the_hotkey_brings_a_hidden_overlay_back() {
+        let mut visibility = Visibility::default();
+        assert!(visibility.is_visible());
+        visibility.toggle();
+        assert!(!visibility.is_visible());
+        visibility.update(
+            &VisibilityConfig::default(),
+            &game(ConnectionState::InGame),
+            1.0,
+        );
+        assert!(!visibility.is_visible());
+        visibility.toggle();
+        assert!(visibility.is_visible());
+    }
+
+    #[test]
+    fn the_hotkey_overrides_a_rule_until_it_stops_applying() {
+        let config = VisibilityConfig {
+            hide_without_game: true,
+            ..VisibilityConfig::default()
+        };
+        let mut visibility = Visibility::default();
+        visibility.update(&config, &game(ConnectionState::NoClient), 1.0);
+        assert!(!visibility.is_visible());
+        visibility.toggle();
+        assert!(visibility.is_visible());
+
+        visibility.update(&config, &game(ConnectionState::InGame), 2.0);
+        visibility.update(&config, &game(ConnectionState::NoClient), 3.0);
+        assert!(!visibility.is_visible());
+    }
+}